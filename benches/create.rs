@@ -0,0 +1,50 @@
+//! Times building trees serially, and across threads with the `parallel` feature:
+//!
+//! ```sh
+//! cargo bench --bench create --features parallel
+//! ```
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use merkle_tree::mk::compact::CompactMerkleTree;
+use merkle_tree::mk::full::FullMerkleTree;
+use merkle_tree::utils::crypto::Sha256Hasher;
+
+const SIZES: [usize; 3] = [1_000, 100_000, 1_000_000];
+
+fn get_data(size: usize) -> Vec<String> {
+    (0..size).map(|i| i.to_string()).collect()
+}
+
+fn bench_create(c: &mut Criterion) {
+    let mut group = c.benchmark_group("create");
+    group.sample_size(10);
+    for size in SIZES {
+        let data = get_data(size);
+        group.throughput(Throughput::Elements(size as u64));
+        group.bench_with_input(BenchmarkId::new("compact", size), &data, |b, data| {
+            b.iter(|| CompactMerkleTree::create(data, Sha256Hasher::new()).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("full", size), &data, |b, data| {
+            b.iter(|| FullMerkleTree::create(data, Sha256Hasher::new()).unwrap())
+        });
+        #[cfg(feature = "parallel")]
+        {
+            group.bench_with_input(
+                BenchmarkId::new("compact_parallel", size),
+                &data,
+                |b, data| {
+                    b.iter(|| {
+                        CompactMerkleTree::create_parallel(data, Sha256Hasher::new()).unwrap()
+                    })
+                },
+            );
+            group.bench_with_input(BenchmarkId::new("full_parallel", size), &data, |b, data| {
+                b.iter(|| FullMerkleTree::create_parallel(data, Sha256Hasher::new()).unwrap())
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_create);
+criterion_main!(benches);
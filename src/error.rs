@@ -0,0 +1,121 @@
+use std::fmt;
+
+use crate::utils::crypto::{HashAlgorithm, HashingMode};
+
+/// Errors returned by the trees and their proofs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MerkleError {
+    /// A tree can't be created without data, nor left without it.
+    EmptyInput,
+    /// There is no leaf at `index` in a tree of `len` leaves.
+    IndexOutOfRange { index: usize, len: usize },
+    /// The sizes asked for a consistency proof don't satisfy `0 < old_size <= new_size <= len`.
+    InvalidSizes {
+        old_size: usize,
+        new_size: usize,
+        len: usize,
+    },
+    /// The leaf indices of a multiproof aren't sorted and unique, or there are none.
+    InvalidIndices,
+    /// A multiproof got a different number of leaf hashes than it proves.
+    LeafCountMismatch { expected: usize, actual: usize },
+    /// The proof doesn't have as many hashes as the tree shape requires.
+    ProofLengthMismatch { expected: usize, actual: usize },
+    /// A hash in the proof isn't as long as the leaf hash.
+    HashLengthMismatch { expected: usize, actual: usize },
+    /// A step of the proof puts the node at another position among its siblings than the
+    /// position of the leaf does.
+    PositionMismatch { step: usize },
+    /// The proof was built with another hash function.
+    AlgorithmMismatch {
+        expected: HashAlgorithm,
+        actual: HashAlgorithm,
+    },
+    /// The proof was built for a tree with other options.
+    OptionsMismatch,
+    /// A tree can't have less than two children per node.
+    InvalidBranchingFactor { branching_factor: usize },
+    /// The leaves of a non-membership proof aren't adjacent or don't surround the value.
+    NotBracketed,
+    /// The balances of a sum tree add up to more than `u64::MAX`.
+    SumOverflow,
+    /// The tree can't hash its nodes in this mode.
+    UnsupportedHashingMode { hashing_mode: HashingMode },
+    /// The proof is well formed but leads to another root.
+    RootMismatch,
+    /// The levels or the root of a snapshot aren't the ones its leaves hash to.
+    CorruptedSnapshot,
+    /// An encoded proof isn't as long as its header says it should be.
+    EncodingLengthMismatch { expected: usize, actual: usize },
+    /// An encoded proof comes from a version of the encoding this one can't read.
+    UnsupportedVersion { version: u8 },
+    /// An encoded proof has an unknown id or a value out of range, or a proof has a value too
+    /// big to be encoded.
+    InvalidEncoding,
+}
+
+impl fmt::Display for MerkleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MerkleError::EmptyInput => write!(f, "a tree must have at least one leaf"),
+            MerkleError::IndexOutOfRange { index, len } => {
+                write!(f, "index {index} is out of range for {len} leaves")
+            }
+            MerkleError::InvalidSizes {
+                old_size,
+                new_size,
+                len,
+            } => write!(
+                f,
+                "invalid sizes {old_size} and {new_size} for a tree of {len} leaves"
+            ),
+            MerkleError::InvalidIndices => {
+                write!(f, "leaf indices must be sorted, unique and not empty")
+            }
+            MerkleError::LeafCountMismatch { expected, actual } => {
+                write!(f, "expected {expected} leaf hashes, got {actual}")
+            }
+            MerkleError::ProofLengthMismatch { expected, actual } => {
+                write!(f, "expected {expected} proof hashes, got {actual}")
+            }
+            MerkleError::HashLengthMismatch { expected, actual } => {
+                write!(f, "expected a hash of {expected} bytes, got {actual}")
+            }
+            MerkleError::PositionMismatch { step } => {
+                write!(
+                    f,
+                    "step {step} of the path has the node at the wrong position"
+                )
+            }
+            MerkleError::AlgorithmMismatch { expected, actual } => {
+                write!(f, "expected a {expected:?} proof, got {actual:?}")
+            }
+            MerkleError::OptionsMismatch => write!(f, "proof was built with other tree options"),
+            MerkleError::InvalidBranchingFactor { branching_factor } => {
+                write!(f, "branching factor {branching_factor} is less than 2")
+            }
+            MerkleError::NotBracketed => {
+                write!(f, "neighbors aren't adjacent or don't surround the value")
+            }
+            MerkleError::SumOverflow => write!(f, "balances add up to more than u64::MAX"),
+            MerkleError::UnsupportedHashingMode { hashing_mode } => {
+                write!(f, "unsupported hashing mode {hashing_mode:?}")
+            }
+            MerkleError::RootMismatch => write!(f, "proof doesn't lead to the expected root"),
+            MerkleError::CorruptedSnapshot => {
+                write!(f, "snapshot hashes don't match its leaves")
+            }
+            MerkleError::EncodingLengthMismatch { expected, actual } => {
+                write!(f, "expected an encoding of {expected} bytes, got {actual}")
+            }
+            MerkleError::UnsupportedVersion { version } => {
+                write!(f, "unsupported encoding version {version}")
+            }
+            MerkleError::InvalidEncoding => write!(f, "invalid proof encoding"),
+        }
+    }
+}
+
+impl std::error::Error for MerkleError {}
+
+pub type Result<T> = std::result::Result<T, MerkleError>;
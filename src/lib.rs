@@ -15,7 +15,7 @@
 //! ### Creating a Merkle Tree
 //!
 //! ```
-//! use merkle_tree::mk::MerkleTree;
+//! use merkle_tree::mk::compact::CompactMerkleTree;
 //! use merkle_tree::utils::crypto::Sha256Hasher;
 //! use merkle_tree::utils::crypto::Blake2s256Hasher;
 //!
@@ -23,7 +23,7 @@
 //! // you can choose between sha and blake 256
 //! let hasher = Sha256Hasher::new();
 //! // let hasher = Blake2s256Hasher::new();
-//! let tree = MerkleTree::create(&data, hasher).unwrap();
+//! let tree = CompactMerkleTree::create(&data, hasher).unwrap();
 //!
 //! assert_eq!(tree.leaves.len(), 5);
 //! println!("Root hash: {:?}", tree.root_hash);
@@ -32,9 +32,7 @@
 //! ### Using your own Hasher
 //!
 //! ```
-//! use merkle_tree::utils::crypto::{Hash, HashableData};
-//! use merkle_tree::Hasher;
-//! use merkle_tree::utils::crypto::Sha256Hasher;
+//! use merkle_tree::utils::crypto::{Hash, HashableData, Hasher};
 //!
 //! struct MyHasher;
 //!
@@ -72,6 +70,7 @@
 //!
 //! This crate is licensed under the MIT license. See the LICENSE file for more details.
 //!
+pub mod error;
 pub mod mk;
 pub mod tree;
 pub mod utils;
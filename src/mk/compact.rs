@@ -1,6 +1,12 @@
-use crate::utils::{crypto::*, num::is_even};
+use crate::error::{MerkleError, Result};
+use crate::utils::crypto::*;
+#[cfg(feature = "parallel")]
+use crate::utils::parallel;
 
-use super::mk::MerkleTree;
+use super::{
+    consistency::ConsistencyProof, index::LeafIndex, mk::MerkleTree, multiproof::MultiProof,
+    options::TreeOptions, proof::MerkleProof, snapshot::TreeSnapshot,
+};
 
 pub struct Node<T> {
     pub value: T,
@@ -18,64 +24,300 @@ where
 }
 
 type MKNode = Node<Hash>;
+/// Hashes a level into its parents, serially or across threads.
+type GetParentNodes<H> = fn(&[MKNode], usize, &H, &TreeOptions) -> Vec<MKNode>;
 
+#[derive(Clone)]
 pub struct CompactMerkleTree<H: Hasher> {
     pub leaves: Vec<MKNode>,
     pub root_hash: Hash,
     pub hasher: H,
+    pub options: TreeOptions,
+    /// Levels above the leaves, `nodes[0]` being their parents and the last one the root.
+    /// Writes go through the `MerkleTree` methods so only the touched paths are rehashed.
+    nodes: Vec<Vec<MKNode>>,
+    leaf_index: Option<LeafIndex>,
 }
 
 impl<H: Hasher> CompactMerkleTree<H> {
-    pub fn create<T: HashableData>(data: &[T], hasher: H) -> Option<Self> {
-        if data.is_empty() {
-            return None;
+    pub fn create<T: HashableData>(data: &[T], hasher: H) -> Result<Self> {
+        CompactMerkleTree::create_with_options(data, hasher, TreeOptions::default())
+    }
+
+    pub fn create_with_options<T: HashableData>(
+        data: &[T],
+        hasher: H,
+        options: TreeOptions,
+    ) -> Result<Self> {
+        let leaves = data
+            .iter()
+            .map(|el| hasher.get_leaf_hash(el, options.hashing_mode))
+            .collect();
+        CompactMerkleTree::create_from_leaf_hashes(leaves, hasher, options)
+    }
+
+    /// Builds the tree from leaves that are already hashed, for instance with
+    /// `get_leaf_hash` or by another implementation.
+    pub fn create_from_leaf_hashes(
+        leaf_hashes: Vec<Hash>,
+        hasher: H,
+        options: TreeOptions,
+    ) -> Result<Self> {
+        CompactMerkleTree::create_from_leaf_hashes_with(
+            leaf_hashes,
+            hasher,
+            options,
+            CompactMerkleTree::get_parent_nodes,
+        )
+    }
+
+    fn create_from_leaf_hashes_with(
+        leaf_hashes: Vec<Hash>,
+        hasher: H,
+        options: TreeOptions,
+        get_parent_nodes: GetParentNodes<H>,
+    ) -> Result<Self> {
+        if leaf_hashes.is_empty() {
+            return Err(MerkleError::EmptyInput);
         }
+        options.validate()?;
 
-        let leaves = CompactMerkleTree::create_leaves_from(data, &hasher);
-        let root_hash = CompactMerkleTree::calculate_root(&leaves, &hasher);
+        let leaves: Vec<MKNode> = leaf_hashes
+            .into_iter()
+            .map(|value| Node { value })
+            .collect();
+        let mut nodes =
+            CompactMerkleTree::calculate_levels(&leaves, &hasher, &options, get_parent_nodes);
+        nodes.remove(0);
+        let root_hash = nodes
+            .last()
+            .map_or(&leaves[0], |level| &level[0])
+            .value
+            .clone();
 
-        Some(Self {
+        Ok(Self {
             leaves,
             root_hash,
             hasher,
+            options,
+            nodes,
+            leaf_index: None,
         })
     }
 
-    fn create_leaves_from<T: HashableData>(data: &[T], hasher: &H) -> Vec<MKNode> {
-        data.iter()
-            .map(|el| Node {
-                value: hasher.get_hash_from_data(el),
-            })
-            .collect()
+    /// Loads a tree saved with `snapshot`, checking its hashes against its leaves.
+    pub fn from_snapshot(snapshot: TreeSnapshot, hasher: H) -> Result<Self> {
+        snapshot.check(&hasher)?;
+        let tree = CompactMerkleTree::create_from_leaf_hashes(
+            snapshot.leaves.clone(),
+            hasher,
+            snapshot.options,
+        )?;
+        if tree.snapshot() != snapshot {
+            return Err(MerkleError::CorruptedSnapshot);
+        }
+        Ok(tree)
     }
 
-    fn calculate_root(leaves: &[MKNode], hasher: &H) -> Hash {
-        let mut nodes = leaves.to_vec();
-
-        while nodes.len() > 1 {
-            nodes = CompactMerkleTree::get_parent_nodes(&nodes, hasher);
+    /// Saves the hashes of the tree, to load it later with `from_snapshot`.
+    pub fn snapshot(&self) -> TreeSnapshot {
+        let get_hashes = |nodes: &[MKNode]| nodes.iter().map(|node| node.value.clone()).collect();
+        TreeSnapshot {
+            algorithm: self.hasher.algorithm(),
+            options: self.options,
+            leaves: get_hashes(&self.leaves),
+            levels: self.nodes.iter().map(|level| get_hashes(level)).collect(),
+            root_hash: self.root_hash.clone(),
         }
+    }
+
+    /// Keeps the positions of the leaves by hash, so looking leaves up by hash doesn't scan
+    /// them, at the cost of memory and of keeping it in sync on every write.
+    pub fn enable_leaf_index(&mut self) {
+        let hashes = self.leaves.iter().map(|leaf| &leaf.value);
+        self.leaf_index = Some(LeafIndex::from_hashes(hashes));
+    }
+
+    pub fn disable_leaf_index(&mut self) {
+        self.leaf_index = None;
+    }
+
+    /// Hashes `data` the way this tree hashes its leaves.
+    pub fn get_leaf_hash<T: HashableData>(&self, data: T) -> Hash {
+        self.hasher.get_leaf_hash(data, self.options.hashing_mode)
+    }
 
-        nodes[0].value.clone()
+    /// Hashes two children the way this tree hashes its interior nodes.
+    pub fn get_node_hash(&self, a: &Hash, b: &Hash) -> Hash {
+        self.hasher.get_node_hash(a, b, self.options.hashing_mode)
     }
 
-    fn get_parent_nodes(nodes: &[MKNode], hasher: &H) -> Vec<MKNode> {
+    fn get_parent_nodes(
+        nodes: &[MKNode],
+        level: usize,
+        hasher: &H,
+        options: &TreeOptions,
+    ) -> Vec<MKNode> {
         nodes
-            .chunks(2)
-            .map(|chunk| match chunk {
-                [a, b] => Node {
-                    value: hasher.get_combined_hash(&a.value, &b.value),
-                },
-                [a] => Node {
-                    value: hasher.get_combined_hash(&a.value, &a.value),
-                },
-                _ => panic!("Unexpected chunk size in get_parent_nodes"),
-            })
+            .chunks(options.branching_factor)
+            .map(|chunk| CompactMerkleTree::get_parent_node(chunk, level, hasher, options))
             .collect()
     }
 
-    fn rebuild_root(&mut self) {
-        self.root_hash = CompactMerkleTree::calculate_root(&self.leaves, &self.hasher);
+    fn get_parent_node(
+        chunk: &[MKNode],
+        level: usize,
+        hasher: &H,
+        options: &TreeOptions,
+    ) -> MKNode {
+        let children: Vec<Hash> = chunk.iter().map(|node| node.value.clone()).collect();
+        Node {
+            value: options.get_parent(&children, level, hasher),
+        }
+    }
+
+    fn calculate_levels(
+        leaves: &[MKNode],
+        hasher: &H,
+        options: &TreeOptions,
+        get_parent_nodes: GetParentNodes<H>,
+    ) -> Vec<Vec<MKNode>> {
+        let mut levels = vec![leaves.to_vec()];
+
+        while levels.last().unwrap().len() > 1 {
+            let level = levels.len() - 1;
+            let parents = get_parent_nodes(&levels[level], level, hasher, options);
+            levels.push(parents);
+        }
+
+        levels
+    }
+
+    fn check_index(&self, index: usize) -> Result<()> {
+        if index >= self.leaves.len() {
+            return Err(MerkleError::IndexOutOfRange {
+                index,
+                len: self.leaves.len(),
+            });
+        }
+        Ok(())
+    }
+
+    fn find_leaf(&self, hash: &Hash) -> Option<usize> {
+        match &self.leaf_index {
+            Some(index) => index.get(hash).first().copied(),
+            None => self.leaves.iter().position(|el| el.value == *hash),
+        }
+    }
+
+    fn get_level(&self, level: usize) -> &[MKNode] {
+        match level {
+            0 => &self.leaves,
+            _ => &self.nodes[level - 1],
+        }
+    }
+
+    /// Rehashes the ancestors of the leaves from `first` to `last` and resizes the levels
+    /// above them to the current number of leaves.
+    fn update_path(&mut self, mut first: usize, mut last: usize) {
+        let mut level = 0;
+
+        loop {
+            let children = self.get_level(level);
+            if children.len() == 1 {
+                break;
+            }
+            let start = self.options.get_group(first, children.len()).start;
+            let end = self.options.get_group(last, children.len()).end;
+            let parents = CompactMerkleTree::get_parent_nodes(
+                &children[start..end],
+                level,
+                &self.hasher,
+                &self.options,
+            );
+            let parents_len = self.options.get_parent_level_size(children.len());
+            first /= self.options.branching_factor;
+            last /= self.options.branching_factor;
+
+            if self.nodes.len() == level {
+                self.nodes.push(Vec::new());
+            }
+            let nodes = &mut self.nodes[level];
+            nodes.truncate(parents_len);
+            for (idx, parent) in (first..).zip(parents) {
+                match nodes.get_mut(idx) {
+                    Some(node) => *node = parent,
+                    None => nodes.push(parent),
+                }
+            }
+            level += 1;
+        }
+
+        self.nodes.truncate(level);
+        self.root_hash = self.get_level(level)[0].value.clone();
+    }
+}
+
+impl CompactMerkleTree<DoubleSha256Hasher> {
+    /// Builds the transaction tree of a Bitcoin block, whose root is the merkle root of its
+    /// header.
+    ///
+    /// `txids` are the transaction ids in internal byte order, that is, reversed from the hex
+    /// strings explorers display. Leaves are the ids themselves, so SPV proofs are verified
+    /// with `verify_proof` against the txid.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use merkle_tree::mk::compact::CompactMerkleTree;
+    /// use merkle_tree::mk::mk::MerkleTree;
+    ///
+    /// let txids = vec![vec![1; 32], vec![2; 32], vec![3; 32]];
+    /// let tree = CompactMerkleTree::create_bitcoin(txids.clone()).unwrap();
+    ///
+    /// let proof = tree.gen_proof(2).unwrap();
+    /// assert!(tree.verify_proof(&txids[2], &proof).is_ok());
+    /// ```
+    pub fn create_bitcoin(txids: Vec<Hash>) -> Result<Self> {
+        CompactMerkleTree::create_from_leaf_hashes(
+            txids,
+            DoubleSha256Hasher::new(),
+            TreeOptions::bitcoin(),
+        )
+    }
+}
+
+#[cfg(feature = "parallel")]
+impl<H: Hasher + Sync> CompactMerkleTree<H> {
+    /// Same as `create`, hashing the leaves and then every level across threads.
+    pub fn create_parallel<T: HashableData + Sync>(data: &[T], hasher: H) -> Result<Self> {
+        CompactMerkleTree::create_parallel_with_options(data, hasher, TreeOptions::default())
+    }
+
+    /// Same as `create_with_options`, hashing the leaves and then every level across threads.
+    pub fn create_parallel_with_options<T: HashableData + Sync>(
+        data: &[T],
+        hasher: H,
+        options: TreeOptions,
+    ) -> Result<Self> {
+        let leaves = parallel::map(data, |el| hasher.get_leaf_hash(el, options.hashing_mode));
+        CompactMerkleTree::create_from_leaf_hashes_with(
+            leaves,
+            hasher,
+            options,
+            CompactMerkleTree::get_parent_nodes_parallel,
+        )
+    }
+
+    fn get_parent_nodes_parallel(
+        nodes: &[MKNode],
+        level: usize,
+        hasher: &H,
+        options: &TreeOptions,
+    ) -> Vec<MKNode> {
+        parallel::map_chunks(nodes, options.branching_factor, |chunk| {
+            CompactMerkleTree::get_parent_node(chunk, level, hasher, options)
+        })
     }
 }
 
@@ -85,75 +327,163 @@ impl<H: Hasher> MerkleTree<MKNode> for CompactMerkleTree<H> {
     }
 
     fn get_leaf_by_hash(&self, hash: &Hash) -> Option<MKNode> {
-        self.leaves.iter().find(|el| el.value == *hash).cloned()
+        self.find_leaf(hash).map(|idx| self.leaves[idx].clone())
+    }
+
+    fn get_leaf_indices(&self, hash: &Hash) -> Vec<usize> {
+        match &self.leaf_index {
+            Some(index) => index.get(hash).to_vec(),
+            None => (0..self.leaves.len())
+                .filter(|idx| self.leaves[*idx].value == *hash)
+                .collect(),
+        }
     }
 
     fn add_leaf<T: HashableData>(&mut self, data: T) {
-        let hash = self.hasher.get_hash_from_data(data);
+        let hash = self.get_leaf_hash(data);
+        let last = self.leaves.len();
+        if let Some(index) = &mut self.leaf_index {
+            index.insert(&hash, last);
+        }
         self.leaves.push(Node { value: hash });
-        self.rebuild_root();
+        self.update_path(last, last);
     }
 
-    fn delete_leaf(&mut self, index: usize) {
-        if self.leaves.get(index).is_some() {
-            self.leaves.remove(index);
-            self.rebuild_root();
+    fn delete_leaf(&mut self, index: usize) -> Result<()> {
+        self.check_index(index)?;
+        if self.leaves.len() == 1 {
+            return Err(MerkleError::EmptyInput);
         }
+        let leaf = self.leaves.remove(index);
+        if let Some(leaf_index) = &mut self.leaf_index {
+            leaf_index.delete(&leaf.value, index);
+        }
+        // every leaf after it moves one position to the left
+        let last = self.leaves.len() - 1;
+        self.update_path(index.min(last), last);
+        Ok(())
     }
 
-    fn update_leaf<T: HashableData>(&mut self, index: usize, data: T) {
-        if let Some(node) = self.leaves.get_mut(index) {
-            node.value = self.hasher.get_hash_from_data(data);
-            self.rebuild_root();
+    fn update_leaf<T: HashableData>(&mut self, index: usize, data: T) -> Result<()> {
+        self.check_index(index)?;
+        let hash = self.get_leaf_hash(data);
+        if let Some(leaf_index) = &mut self.leaf_index {
+            leaf_index.remove(&self.leaves[index].value, index);
+            leaf_index.insert(&hash, index);
         }
+        self.leaves[index].value = hash;
+        self.update_path(index, index);
+        Ok(())
     }
 
-    fn gen_proof(&self, mut leaf_idx: usize) -> Option<Vec<Hash>> {
-        let mut proof: Vec<Hash> = Vec::new();
+    fn gen_proof(&self, leaf_idx: usize) -> Result<MerkleProof> {
+        self.check_index(leaf_idx)?;
 
-        if self.leaves.get(leaf_idx).is_none() {
-            return None;
-        }
+        let mut siblings: Vec<Hash> = Vec::new();
+        let mut idx = leaf_idx;
 
-        let mut nodes = self.leaves.clone();
+        for level in 0..self.nodes.len() {
+            let nodes = self.get_level(level);
+            let group = self.options.get_group(idx, nodes.len());
+            let len = group.len();
 
-        while nodes.len() > 1 {
-            let sibling_idx = if is_even(leaf_idx) {
-                leaf_idx + 1
-            } else {
-                leaf_idx - 1
-            };
+            if !self.options.is_promoted(idx, nodes.len()) {
+                let last = &nodes[group.end - 1].value;
+                let padding = self.options.get_padding(last, len, level, &self.hasher);
+                siblings.extend(
+                    group
+                        .filter(|sibling_idx| *sibling_idx != idx)
+                        .map(|sibling_idx| nodes[sibling_idx].value.clone())
+                        .chain(padding),
+                );
+            }
+            idx /= self.options.branching_factor;
+        }
 
-            let sibling_value = nodes
-                .get(sibling_idx)
-                .map(|node| node.value.clone())
-                .unwrap_or(nodes[leaf_idx].value.clone());
+        Ok(MerkleProof::new(
+            leaf_idx,
+            self.leaves.len(),
+            self.hasher.algorithm(),
+            self.options,
+            siblings,
+        ))
+    }
 
-            proof.push(sibling_value);
-            nodes = CompactMerkleTree::get_parent_nodes(&nodes, &self.hasher);
-            leaf_idx /= 2;
+    fn verify_proof(&self, leaf_hash: &Hash, proof: &MerkleProof) -> Result<()> {
+        if proof.options != self.options {
+            return Err(MerkleError::OptionsMismatch);
         }
+        proof.verify(&self.root_hash, leaf_hash, &self.hasher)
+    }
 
-        Some(proof)
+    fn gen_multi_proof(&self, leaf_indices: &[usize]) -> Result<MultiProof> {
+        MultiProof::generate(
+            leaf_indices,
+            self.leaves.len(),
+            self.hasher.algorithm(),
+            self.options,
+            |level, idx| self.get_level(level)[idx].value.clone(),
+        )
     }
 
-    fn verify_proof(&self, leaf_hash: &Hash, mut leaf_idx: usize, proof: Vec<Hash>) -> bool {
-        let mut leaf_hash = leaf_hash.clone();
-        for hash in proof {
-            if is_even(leaf_idx) {
-                leaf_hash = self.hasher.get_combined_hash(&leaf_hash, &hash);
-            } else {
-                leaf_hash = self.hasher.get_combined_hash(&hash, &leaf_hash);
-            }
-            leaf_idx /= 2;
+    fn verify_multi_proof(&self, leaf_hashes: &[Hash], proof: &MultiProof) -> Result<()> {
+        if proof.options != self.options {
+            return Err(MerkleError::OptionsMismatch);
         }
-        leaf_hash == self.root_hash
+        proof.verify(&self.root_hash, leaf_hashes, &self.hasher)
     }
 
-    fn contains_hash(&self, hash: &Hash) -> Option<(usize, Vec<Hash>)> {
-        let leaf_index = self.leaves.iter().position(|el| el.value == *hash)?;
+    fn gen_consistency_proof(&self, old_size: usize, new_size: usize) -> Result<ConsistencyProof> {
+        if old_size == 0 || old_size > new_size || new_size > self.leaves.len() {
+            return Err(MerkleError::InvalidSizes {
+                old_size,
+                new_size,
+                len: self.leaves.len(),
+            });
+        }
+
+        let path = ConsistencyProof::gen_path(
+            old_size,
+            new_size,
+            &self.options,
+            &self.hasher,
+            |level, idx| self.get_level(level)[idx].value.clone(),
+        );
+
+        Ok(ConsistencyProof {
+            old_size,
+            new_size,
+            algorithm: self.hasher.algorithm(),
+            options: self.options,
+            last_leaf: self.leaves[old_size - 1].value.clone(),
+            path,
+        })
+    }
+
+    fn contains_hash(&self, hash: &Hash) -> Option<(usize, MerkleProof)> {
+        let leaf_index = self.find_leaf(hash)?;
 
         let proof = self.gen_proof(leaf_index).unwrap();
         Some((leaf_index, proof))
     }
 }
+
+#[cfg(feature = "serde")]
+impl<H: Hasher> serde::Serialize for CompactMerkleTree<H> {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        self.snapshot().serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, H: Hasher + Default> serde::Deserialize<'de> for CompactMerkleTree<H> {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        let snapshot = TreeSnapshot::deserialize(deserializer)?;
+        CompactMerkleTree::from_snapshot(snapshot, H::default()).map_err(serde::de::Error::custom)
+    }
+}
@@ -0,0 +1,166 @@
+use crate::error::{MerkleError, Result};
+use crate::utils::crypto::*;
+
+use super::{
+    options::TreeOptions,
+    proof::{check_algorithm, check_hash_lengths},
+};
+
+/// Proves that the tree with `old_size` leaves is a prefix of the tree with `new_size` leaves.
+///
+/// Every node on the path of the last old leaf that lies to its left covers only old leaves,
+/// so it is the same in both trees. Hashing the last old leaf with those nodes gives back the
+/// old root, while hashing it with the whole path gives the new one. If both match, the new
+/// tree was built by appending to the old one without rewriting any of its leaves.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConsistencyProof {
+    pub old_size: usize,
+    pub new_size: usize,
+    pub algorithm: HashAlgorithm,
+    pub options: TreeOptions,
+    /// Hash of the leaf at `old_size - 1`.
+    pub last_leaf: Hash,
+    /// Siblings of `last_leaf` in the new tree, from the leaf level up and left to right within
+    /// a level. Levels where the node has no sibling are left out.
+    pub path: Vec<Hash>,
+}
+
+impl ConsistencyProof {
+    /// The number of hashes `path` has to have to go from `old_size` to `new_size`.
+    ///
+    /// `options` must be valid, a branching factor below 2 never reaches the root.
+    pub fn expected_path_len(old_size: usize, new_size: usize, options: &TreeOptions) -> usize {
+        let mut idx = old_size - 1;
+        let mut level_size = new_size;
+        let mut len = 0;
+
+        while level_size > 1 {
+            len += options.get_group(idx, level_size).len() - 1;
+            idx /= options.branching_factor;
+            level_size = options.get_parent_level_size(level_size);
+        }
+
+        len
+    }
+
+    /// The path of the leaf at `old_size - 1` in the tree of the first `new_size` leaves of a
+    /// tree whose nodes `get_node` reads by level and index.
+    ///
+    /// The tree of those leaves only differs from the whole one on its right edge, where
+    /// groups may be cut short, so the last node of every level is hashed again and the others
+    /// are read with `get_node`.
+    pub(crate) fn gen_path<H: Hasher>(
+        old_size: usize,
+        new_size: usize,
+        options: &TreeOptions,
+        hasher: &H,
+        get_node: impl Fn(usize, usize) -> Hash,
+    ) -> Vec<Hash> {
+        let mut idx = old_size - 1;
+        let mut level_size = new_size;
+        let mut level = 0;
+        let mut last_node = get_node(0, new_size - 1);
+        let mut path = Vec::new();
+
+        while level_size > 1 {
+            let get_prefix_node = |node_idx: usize| match node_idx + 1 == level_size {
+                true => last_node.clone(),
+                false => get_node(level, node_idx),
+            };
+            for sibling_idx in options.get_group(idx, level_size) {
+                if sibling_idx != idx {
+                    path.push(get_prefix_node(sibling_idx));
+                }
+            }
+            let last_group: Vec<Hash> = options
+                .get_group(level_size - 1, level_size)
+                .map(get_prefix_node)
+                .collect();
+            last_node = options.get_parent(&last_group, level, hasher);
+
+            idx /= options.branching_factor;
+            level_size = options.get_parent_level_size(level_size);
+            level += 1;
+        }
+
+        path
+    }
+
+    /// Recomputes both roots, failing if the path doesn't fit the tree sizes.
+    pub fn compute_roots<H: Hasher>(&self, hasher: &H) -> Result<(Hash, Hash)> {
+        if self.old_size == 0 || self.old_size > self.new_size {
+            return Err(MerkleError::InvalidSizes {
+                old_size: self.old_size,
+                new_size: self.new_size,
+                len: self.new_size,
+            });
+        }
+        self.options.validate()?;
+        let expected =
+            ConsistencyProof::expected_path_len(self.old_size, self.new_size, &self.options);
+        if expected != self.path.len() {
+            return Err(MerkleError::ProofLengthMismatch {
+                expected,
+                actual: self.path.len(),
+            });
+        }
+        check_hash_lengths(self.last_leaf.len(), &self.path)?;
+
+        let mut idx = self.old_size - 1;
+        let mut old_level_size = self.old_size;
+        let mut new_level_size = self.new_size;
+        let mut old_hash = self.last_leaf.clone();
+        let mut new_hash = self.last_leaf.clone();
+        let mut path = self.path.iter();
+        let mut level = 0;
+
+        while new_level_size > 1 {
+            // the length of the path was checked against the sizes above
+            let group = self.options.get_group(idx, new_level_size);
+            let left: Vec<Hash> = path.by_ref().take(idx - group.start).cloned().collect();
+            let right = path.by_ref().take(group.end - idx - 1).cloned();
+
+            // the node is the last one of the old level, so it only had the left siblings there
+            if old_level_size > 1 {
+                let children = [left.clone(), vec![old_hash]].concat();
+                old_hash = self.options.get_parent(&children, level, hasher);
+            }
+            let children: Vec<Hash> = left.into_iter().chain([new_hash]).chain(right).collect();
+            new_hash = self.options.get_parent(&children, level, hasher);
+
+            idx /= self.options.branching_factor;
+            old_level_size = self.options.get_parent_level_size(old_level_size);
+            new_level_size = self.options.get_parent_level_size(new_level_size);
+            level += 1;
+        }
+
+        Ok((old_hash, new_hash))
+    }
+
+    /// Verifies that the tree with root `old_root` is a prefix of the one with root `new_root`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use merkle_tree::mk::compact::CompactMerkleTree;
+    /// use merkle_tree::mk::mk::MerkleTree;
+    /// use merkle_tree::utils::crypto::Sha256Hasher;
+    ///
+    /// let mut tree = CompactMerkleTree::create(&["hello", "how", "are"], Sha256Hasher::new()).unwrap();
+    /// let old_root = tree.root_hash.clone();
+    /// tree.add_leaf("you");
+    /// tree.add_leaf("doing");
+    ///
+    /// let proof = tree.gen_consistency_proof(3, 5).unwrap();
+    /// assert!(proof.verify(&old_root, &tree.root_hash, &Sha256Hasher::new()).is_ok());
+    /// ```
+    pub fn verify<H: Hasher>(&self, old_root: &Hash, new_root: &Hash, hasher: &H) -> Result<()> {
+        check_algorithm(hasher, self.algorithm)?;
+
+        let (old, new) = self.compute_roots(hasher)?;
+        if old != *old_root || new != *new_root {
+            return Err(MerkleError::RootMismatch);
+        }
+        Ok(())
+    }
+}
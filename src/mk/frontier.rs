@@ -0,0 +1,135 @@
+use crate::error::{MerkleError, Result};
+use crate::utils::{crypto::*, num::is_even};
+
+/// An append-only tree of fixed depth that only keeps its left frontier, as the Ethereum
+/// deposit contract does.
+///
+/// The tree always has `2^depth` leaves, the ones not appended yet being zero hashes, so it
+/// takes `O(depth)` memory no matter how many leaves are appended. Leaves can't be read back
+/// nor proven, only the root is known.
+#[derive(Clone)]
+pub struct FrontierMerkleTree<H: Hasher> {
+    pub hasher: H,
+    pub hashing_mode: HashingMode,
+    pub depth: usize,
+    pub root_hash: Hash,
+    count: usize,
+    /// The last left node completed on every level, still waiting for its right sibling.
+    branch: Vec<Hash>,
+    /// The hashes of empty subtrees for every level.
+    zero_hashes: Vec<Hash>,
+}
+
+impl<H: Hasher> FrontierMerkleTree<H> {
+    /// # Panics
+    ///
+    /// Panics if `depth` is too big for the number of leaves to fit in a `usize`.
+    pub fn new(depth: usize, hasher: H) -> Self {
+        FrontierMerkleTree::new_with_hashing_mode(depth, hasher, HashingMode::default())
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `depth` is too big for the number of leaves to fit in a `usize`.
+    pub fn new_with_hashing_mode(depth: usize, hasher: H, hashing_mode: HashingMode) -> Self {
+        assert!(depth < usize::BITS as usize, "depth {depth} is too big");
+
+        let len = hasher.get_leaf_hash([], hashing_mode).len();
+        let mut zero_hashes = vec![vec![0; len]];
+        for level in 0..depth {
+            let zero = &zero_hashes[level];
+            zero_hashes.push(hasher.get_node_hash(zero, zero, hashing_mode));
+        }
+
+        Self {
+            root_hash: zero_hashes[depth].clone(),
+            branch: zero_hashes[..depth].to_vec(),
+            hasher,
+            hashing_mode,
+            depth,
+            count: 0,
+            zero_hashes,
+        }
+    }
+
+    /// The number of leaves appended so far.
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// The number of leaves the tree can take.
+    pub fn capacity(&self) -> usize {
+        1 << self.depth
+    }
+
+    /// Hashes `data` the way this tree hashes its leaves.
+    pub fn get_leaf_hash<T: HashableData>(&self, data: T) -> Hash {
+        self.hasher.get_leaf_hash(data, self.hashing_mode)
+    }
+
+    /// Appends a leaf and updates the root, failing if the tree is full.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use merkle_tree::mk::frontier::FrontierMerkleTree;
+    /// use merkle_tree::utils::crypto::Sha256Hasher;
+    ///
+    /// let mut tree = FrontierMerkleTree::new(1, Sha256Hasher::new());
+    /// tree.append("hello").unwrap();
+    /// tree.append("world").unwrap();
+    /// assert!(tree.append("full").is_err());
+    /// ```
+    pub fn append<T: HashableData>(&mut self, data: T) -> Result<()> {
+        if self.count == self.capacity() {
+            return Err(MerkleError::IndexOutOfRange {
+                index: self.count,
+                len: self.capacity(),
+            });
+        }
+
+        let mut node = self.get_leaf_hash(data);
+        let mut size = self.count + 1;
+        self.count += 1;
+        for level in 0..self.depth {
+            // the node is a left child, its parent will be known once its sibling comes
+            if !is_even(size) {
+                self.branch[level] = node;
+                self.root_hash = self.calculate_root();
+                return Ok(());
+            }
+            node = self
+                .hasher
+                .get_node_hash(&self.branch[level], &node, self.hashing_mode);
+            size /= 2;
+        }
+
+        // every level got completed, so the tree is full and the node is its root
+        self.root_hash = node;
+        Ok(())
+    }
+
+    fn calculate_root(&self) -> Hash {
+        let mut node = self.zero_hashes[0].clone();
+        let mut size = self.count;
+
+        for level in 0..self.depth {
+            node = match is_even(size) {
+                true => {
+                    self.hasher
+                        .get_node_hash(&node, &self.zero_hashes[level], self.hashing_mode)
+                }
+                false => self
+                    .hasher
+                    .get_node_hash(&self.branch[level], &node, self.hashing_mode),
+            };
+            size /= 2;
+        }
+
+        node
+    }
+}
@@ -1,156 +1,639 @@
+use crate::error::{MerkleError, Result};
 use crate::tree::*;
 use crate::utils::crypto::*;
-use crate::utils::num;
-use std::rc::Rc;
+#[cfg(feature = "parallel")]
+use crate::utils::parallel;
 
-use super::mk::MerkleTree;
+use super::{
+    consistency::ConsistencyProof,
+    index::LeafIndex,
+    mk::MerkleTree,
+    multiproof::MultiProof,
+    options::{OddNodePolicy, TreeOptions},
+    proof::MerkleProof,
+    snapshot::TreeSnapshot,
+};
 
-type MKNode = TreeNode<Hash>;
+type MKNode = Node<Hash>;
+/// Hashes the groups of a level into their parents, serially or across threads.
+type GetParentHashes<H> = fn(&Arena<Hash>, &[NodeId], usize, &H, &TreeOptions) -> Vec<Hash>;
+
+/// A Merkle tree that keeps every node, linked to its parent and children, in an `Arena`.
+#[derive(Clone)]
 pub struct FullMerkleTree<H: Hasher> {
     pub hasher: H,
-    pub tree: MKNode,
-    pub leaves: Vec<MKNode>,
+    /// Ids of the leaves in the arena, in order.
+    pub leaves: Vec<NodeId>,
+    /// Id of the root in the arena.
+    pub root: NodeId,
     pub root_hash: Hash,
+    pub options: TreeOptions,
+    /// Adding leaves leaves the replaced nodes of the right edge behind, so the arena is
+    /// rebuilt once it doubles the size it had when it was last built.
+    arena: Arena<Hash>,
+    built_len: usize,
+    leaf_index: Option<LeafIndex>,
 }
 
 impl<H: Hasher> FullMerkleTree<H> {
-    pub fn create<T: HashableData>(data: &[T], hasher: H) -> Option<Self> {
-        if data.is_empty() {
-            return None;
-        }
-        let leaves = FullMerkleTree::create_leaves_from(data, &hasher);
+    pub fn create<T: HashableData>(data: &[T], hasher: H) -> Result<Self> {
+        FullMerkleTree::create_with_options(data, hasher, TreeOptions::default())
+    }
+
+    pub fn create_with_options<T: HashableData>(
+        data: &[T],
+        hasher: H,
+        options: TreeOptions,
+    ) -> Result<Self> {
+        let leaf_hashes = data
+            .iter()
+            .map(|el| hasher.get_leaf_hash(el, options.hashing_mode))
+            .collect();
+        FullMerkleTree::create_from_leaf_hashes_with(
+            leaf_hashes,
+            hasher,
+            options,
+            FullMerkleTree::get_parent_hashes,
+        )
+    }
 
-        let tree = FullMerkleTree::create_tree(leaves.clone(), &hasher);
-        let root_hash = tree.borrow().value.clone();
+    fn create_from_leaf_hashes_with(
+        leaf_hashes: Vec<Hash>,
+        hasher: H,
+        options: TreeOptions,
+        get_parent_hashes: GetParentHashes<H>,
+    ) -> Result<Self> {
+        if leaf_hashes.is_empty() {
+            return Err(MerkleError::EmptyInput);
+        }
+        options.validate()?;
+        let (arena, leaves, root) =
+            FullMerkleTree::create_tree_with(leaf_hashes, &hasher, &options, get_parent_hashes);
 
-        Some(Self {
-            tree,
+        Ok(Self {
+            root_hash: arena[root].value.clone(),
+            built_len: arena.len(),
+            arena,
             leaves,
-            root_hash,
+            root,
             hasher,
+            options,
+            leaf_index: None,
         })
     }
 
-    fn create_leaves_from<T: HashableData>(data: &[T], hasher: &H) -> Vec<MKNode> {
-        data.iter()
-            .map(|el| Node::new(hasher.get_hash_from_data(el), None, None, None))
-            .collect()
+    /// The nodes of the tree, to walk it from `leaves` or `root`.
+    pub fn arena(&self) -> &Arena<Hash> {
+        &self.arena
+    }
+
+    /// Loads a tree saved with `snapshot`, checking its hashes against its leaves.
+    pub fn from_snapshot(snapshot: TreeSnapshot, hasher: H) -> Result<Self> {
+        snapshot.check(&hasher)?;
+        let options = snapshot.options;
+        let (arena, leaves, root) =
+            FullMerkleTree::create_tree(snapshot.leaves.clone(), &hasher, &options);
+        let tree = Self {
+            root_hash: arena[root].value.clone(),
+            built_len: arena.len(),
+            arena,
+            leaves,
+            root,
+            hasher,
+            options,
+            leaf_index: None,
+        };
+        if tree.snapshot() != snapshot {
+            return Err(MerkleError::CorruptedSnapshot);
+        }
+        Ok(tree)
     }
 
-    fn create_tree(mut leaves: Vec<MKNode>, hasher: &H) -> MKNode {
-        while leaves.len() > 1 {
-            leaves = leaves
-                .chunks(2)
-                .map(|el| match el {
-                    [a, b] => Self::create_node(a, b, hasher),
-                    // hash with itself
-                    [a] => Self::create_node(a, &Node::<Hash>::clone(a), hasher),
-                    _ => panic!("unexpected chunk size"),
+    /// Saves the hashes of the tree, to load it later with `from_snapshot`.
+    pub fn snapshot(&self) -> TreeSnapshot {
+        let mut levels = Vec::new();
+        let mut nodes = self.leaves.clone();
+        while nodes.len() > 1 {
+            let len = nodes.len();
+            nodes = (0..len)
+                .step_by(self.options.branching_factor)
+                .map(|idx| {
+                    if self.options.is_promoted(idx, len) {
+                        nodes[idx]
+                    } else {
+                        // every node but the root has a parent
+                        self.arena[nodes[idx]].parent.unwrap()
+                    }
                 })
                 .collect();
+            levels.push(
+                nodes
+                    .iter()
+                    .map(|id| self.arena[*id].value.clone())
+                    .collect(),
+            );
+        }
+
+        TreeSnapshot {
+            algorithm: self.hasher.algorithm(),
+            options: self.options,
+            leaves: self
+                .leaves
+                .iter()
+                .map(|id| self.arena[*id].value.clone())
+                .collect(),
+            levels,
+            root_hash: self.root_hash.clone(),
+        }
+    }
+
+    /// Keeps the positions of the leaves by hash, so looking leaves up by hash doesn't scan
+    /// them, at the cost of memory and of keeping it in sync on every write.
+    pub fn enable_leaf_index(&mut self) {
+        let hashes = self.leaves.iter().map(|id| &self.arena[*id].value);
+        self.leaf_index = Some(LeafIndex::from_hashes(hashes));
+    }
+
+    pub fn disable_leaf_index(&mut self) {
+        self.leaf_index = None;
+    }
+
+    /// Hashes `data` the way this tree hashes its leaves.
+    pub fn get_leaf_hash<T: HashableData>(&self, data: T) -> Hash {
+        self.hasher.get_leaf_hash(data, self.options.hashing_mode)
+    }
+
+    /// Hashes two children the way this tree hashes its interior nodes.
+    pub fn get_node_hash(&self, a: &Hash, b: &Hash) -> Hash {
+        self.hasher.get_node_hash(a, b, self.options.hashing_mode)
+    }
+
+    /// Builds a new arena from the leaf hashes, returning it with the ids of the leaves and
+    /// the root.
+    fn create_tree(
+        leaf_hashes: Vec<Hash>,
+        hasher: &H,
+        options: &TreeOptions,
+    ) -> (Arena<Hash>, Vec<NodeId>, NodeId) {
+        FullMerkleTree::create_tree_with(
+            leaf_hashes,
+            hasher,
+            options,
+            FullMerkleTree::get_parent_hashes,
+        )
+    }
+
+    fn create_tree_with(
+        leaf_hashes: Vec<Hash>,
+        hasher: &H,
+        options: &TreeOptions,
+        get_parent_hashes: GetParentHashes<H>,
+    ) -> (Arena<Hash>, Vec<NodeId>, NodeId) {
+        let mut arena = Arena::with_capacity(2 * leaf_hashes.len());
+        let leaves: Vec<NodeId> = leaf_hashes
+            .into_iter()
+            .map(|hash| arena.push(hash, Vec::new()))
+            .collect();
+
+        let mut nodes = leaves.clone();
+        let mut level = 0;
+        while nodes.len() > 1 {
+            // the hashes of a level don't depend on each other, only linking them does
+            let hashes = get_parent_hashes(&arena, &nodes, level, hasher, options);
+            nodes = nodes
+                .chunks(options.branching_factor)
+                .zip(hashes)
+                .map(|(children, value)| {
+                    FullMerkleTree::link_group_node(
+                        &mut arena, children, value, level, hasher, options,
+                    )
+                })
+                .collect();
+            level += 1;
         }
 
         // there has to be a first, otherwise the while would keep running
-        return leaves.first().unwrap().to_owned();
+        (arena, leaves, nodes[0])
     }
 
-    fn create_node(a: &MKNode, b: &MKNode, hasher: &H) -> MKNode {
-        let node = Node::new(
-            hasher.get_combined_hash(&a.borrow().value, &b.borrow().value),
-            Some(vec![Rc::clone(a), Rc::clone(b)]),
-            None,
-            None,
-        );
-        Node::set_parent_and_siblings(&node, &[a, b]);
+    fn get_parent_hashes(
+        arena: &Arena<Hash>,
+        nodes: &[NodeId],
+        level: usize,
+        hasher: &H,
+        options: &TreeOptions,
+    ) -> Vec<Hash> {
+        nodes
+            .chunks(options.branching_factor)
+            .map(|children| {
+                FullMerkleTree::get_parent_hash(arena, children, level, hasher, options)
+            })
+            .collect()
+    }
+
+    fn get_parent_hash(
+        arena: &Arena<Hash>,
+        children: &[NodeId],
+        level: usize,
+        hasher: &H,
+        options: &TreeOptions,
+    ) -> Hash {
+        let children: Vec<Hash> = children
+            .iter()
+            .map(|child| arena[*child].value.clone())
+            .collect();
+        options.get_parent(&children, level, hasher)
+    }
+
+    /// Creates the parent of `children` at `level`, completing them if they don't fill a group.
+    fn create_group_node(
+        arena: &mut Arena<Hash>,
+        children: &[NodeId],
+        level: usize,
+        hasher: &H,
+        options: &TreeOptions,
+    ) -> NodeId {
+        let value = FullMerkleTree::get_parent_hash(arena, children, level, hasher, options);
+        FullMerkleTree::link_group_node(arena, children, value, level, hasher, options)
+    }
+
+    /// Adds the parent of `children` at `level`, already hashed to `value`, with the nodes
+    /// completing the group.
+    fn link_group_node(
+        arena: &mut Arena<Hash>,
+        children: &[NodeId],
+        value: Hash,
+        level: usize,
+        hasher: &H,
+        options: &TreeOptions,
+    ) -> NodeId {
+        let missing = options.branching_factor - children.len();
+        let last = *children.last().unwrap();
+        let padding: Vec<NodeId> = match options.odd_node_policy {
+            _ if missing == 0 => Vec::new(),
+            // the last node is listed again, so updating it updates its copies
+            OddNodePolicy::Duplicate => vec![last; missing],
+            // goes up as is, it gets its parent on a later level
+            OddNodePolicy::Promote if children.len() == 1 => return last,
+            OddNodePolicy::Promote => Vec::new(),
+            OddNodePolicy::PadToPowerOfTwo => {
+                let zero = options.get_zero_hash(level, arena[last].value.len(), hasher);
+                (0..missing)
+                    .map(|_| arena.push(zero.clone(), Vec::new()))
+                    .collect()
+            }
+        };
+        arena.push(value, [children, &padding].concat())
+    }
+
+    fn get_siblings(&self, leaf: NodeId) -> Vec<Hash> {
+        let mut siblings = Vec::new();
+        let path: Vec<NodeId> = self.arena.get_path(leaf).collect();
+
+        for pair in path.windows(2) {
+            let (node, parent) = (pair[0], pair[1]);
+            let children = &self.arena[parent].children;
+            // copies of the node come after it, so they are siblings too
+            let position = children.iter().position(|child| *child == node).unwrap();
+            for (idx, child) in children.iter().enumerate() {
+                if idx != position {
+                    siblings.push(self.arena[*child].value.clone());
+                }
+            }
+        }
+
+        siblings
+    }
+
+    /// Finds the node at `idx` within `level` by climbing from its leftmost leaf.
+    fn get_node(
+        arena: &Arena<Hash>,
+        leaves: &[NodeId],
+        options: &TreeOptions,
+        level: usize,
+        idx: usize,
+    ) -> NodeId {
+        let mut node_idx = idx * options.branching_factor.pow(level as u32);
+        let mut level_size = leaves.len();
+        let mut node = leaves[node_idx];
+
+        for _ in 0..level {
+            // a promoted node also stands for its parent
+            if !options.is_promoted(node_idx, level_size) {
+                node = arena[node].parent.unwrap();
+            }
+            node_idx /= options.branching_factor;
+            level_size = options.get_parent_level_size(level_size);
+        }
 
         node
     }
 
+    fn find_leaf(&self, hash: &Hash) -> Option<usize> {
+        match &self.leaf_index {
+            Some(index) => index.get(hash).first().copied(),
+            None => self
+                .leaves
+                .iter()
+                .position(|id| self.arena[*id].value == *hash),
+        }
+    }
+
+    fn check_index(&self, index: usize) -> Result<()> {
+        if index >= self.leaves.len() {
+            return Err(MerkleError::IndexOutOfRange {
+                index,
+                len: self.leaves.len(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Rehashes the nodes above the leaf at `idx` through their parent links.
+    fn update_ancestors(&mut self, idx: usize) {
+        let mut node = self.leaves[idx];
+        let mut node_idx = idx;
+        let mut level_size = self.leaves.len();
+
+        while level_size > 1 {
+            // a promoted node is its own parent on this level
+            if !self.options.is_promoted(node_idx, level_size) {
+                node = self.arena[node].parent.unwrap();
+                let hashes: Vec<Hash> = self.arena[node]
+                    .children
+                    .iter()
+                    .map(|child| self.arena[*child].value.clone())
+                    .collect();
+                self.arena[node].value = self
+                    .hasher
+                    .get_children_hash(&hashes, self.options.hashing_mode);
+            }
+            node_idx /= self.options.branching_factor;
+            level_size = self.options.get_parent_level_size(level_size);
+        }
+
+        self.root_hash = self.arena[node].value.clone();
+        self.root = node;
+    }
+
+    /// Hooks the last leaf into the tree, creating only the nodes on the right edge.
+    fn extend_right_spine(&mut self) {
+        let (new_leaf, old_leaves) = self.leaves.split_last().unwrap();
+        let new_idx = old_leaves.len();
+
+        // the left siblings of the new path are looked up before any link changes
+        let mut left_siblings = Vec::new();
+        let (mut idx, mut level_size, mut level) = (new_idx, self.leaves.len(), 0);
+        while level_size > 1 {
+            let group = self.options.get_group(idx, level_size);
+            left_siblings.push(
+                (group.start..idx)
+                    .map(|sibling_idx| {
+                        FullMerkleTree::<H>::get_node(
+                            &self.arena,
+                            old_leaves,
+                            &self.options,
+                            level,
+                            sibling_idx,
+                        )
+                    })
+                    .collect::<Vec<NodeId>>(),
+            );
+            idx /= self.options.branching_factor;
+            level_size = self.options.get_parent_level_size(level_size);
+            level += 1;
+        }
+
+        // the new node is always the last one of its level
+        let mut node = *new_leaf;
+        for (level, mut children) in left_siblings.into_iter().enumerate() {
+            children.push(node);
+            node = FullMerkleTree::create_group_node(
+                &mut self.arena,
+                &children,
+                level,
+                &self.hasher,
+                &self.options,
+            );
+        }
+        self.arena[node].parent = None;
+
+        self.root_hash = self.arena[node].value.clone();
+        self.root = node;
+    }
+
     fn rebuild_tree(&mut self) {
-        let tree = FullMerkleTree::create_tree(self.leaves.clone(), &self.hasher);
-        let root_hash = tree.borrow().value.clone();
-        self.tree = tree;
-        self.root_hash = root_hash;
+        let leaf_hashes = self
+            .leaves
+            .iter()
+            .map(|id| self.arena[*id].value.clone())
+            .collect();
+        let (arena, leaves, root) =
+            FullMerkleTree::create_tree(leaf_hashes, &self.hasher, &self.options);
+        self.root_hash = arena[root].value.clone();
+        self.built_len = arena.len();
+        self.arena = arena;
+        self.leaves = leaves;
+        self.root = root;
+    }
+}
+
+#[cfg(feature = "parallel")]
+impl<H: Hasher + Sync> FullMerkleTree<H> {
+    /// Same as `create`, hashing the leaves and then every level across threads.
+    pub fn create_parallel<T: HashableData + Sync>(data: &[T], hasher: H) -> Result<Self> {
+        FullMerkleTree::create_parallel_with_options(data, hasher, TreeOptions::default())
+    }
+
+    /// Same as `create_with_options`, hashing the leaves and then every level across threads.
+    pub fn create_parallel_with_options<T: HashableData + Sync>(
+        data: &[T],
+        hasher: H,
+        options: TreeOptions,
+    ) -> Result<Self> {
+        let leaf_hashes = parallel::map(data, |el| hasher.get_leaf_hash(el, options.hashing_mode));
+        FullMerkleTree::create_from_leaf_hashes_with(
+            leaf_hashes,
+            hasher,
+            options,
+            FullMerkleTree::get_parent_hashes_parallel,
+        )
+    }
+
+    fn get_parent_hashes_parallel(
+        arena: &Arena<Hash>,
+        nodes: &[NodeId],
+        level: usize,
+        hasher: &H,
+        options: &TreeOptions,
+    ) -> Vec<Hash> {
+        // the hashes of a level don't depend on each other, only linking them does
+        parallel::map_chunks(nodes, options.branching_factor, |children| {
+            FullMerkleTree::get_parent_hash(arena, children, level, hasher, options)
+        })
     }
 }
 
 impl<H: Hasher> MerkleTree<MKNode> for FullMerkleTree<H> {
     fn get_leaf_by_idx(&self, idx: usize) -> Option<MKNode> {
-        self.leaves.get(idx).cloned()
+        self.leaves.get(idx).map(|id| self.arena[*id].clone())
     }
 
     fn get_leaf_by_hash(&self, hash: &Hash) -> Option<MKNode> {
-        self.leaves
-            .iter()
-            .find(|el| el.borrow().value == *hash)
-            .cloned()
+        self.find_leaf(hash)
+            .map(|idx| self.arena[self.leaves[idx]].clone())
     }
 
-    fn add_leaf<T: HashableData>(&mut self, data: T) {
-        let hash = self.hasher.get_hash_from_data(data);
-        let node = Node::new(hash, None, None, None);
-        self.leaves.push(node);
-        self.rebuild_tree();
+    fn get_leaf_indices(&self, hash: &Hash) -> Vec<usize> {
+        match &self.leaf_index {
+            Some(index) => index.get(hash).to_vec(),
+            None => (0..self.leaves.len())
+                .filter(|idx| self.arena[self.leaves[*idx]].value == *hash)
+                .collect(),
+        }
     }
 
-    fn delete_leaf(&mut self, index: usize) {
-        if self.leaves.get(index).is_some() {
-            self.leaves.remove(index);
+    fn add_leaf<T: HashableData>(&mut self, data: T) {
+        let hash = self.get_leaf_hash(data);
+        if let Some(index) = &mut self.leaf_index {
+            index.insert(&hash, self.leaves.len());
+        }
+        let leaf = self.arena.push(hash, Vec::new());
+        self.leaves.push(leaf);
+        self.extend_right_spine();
+        if self.arena.len() > 2 * self.built_len {
             self.rebuild_tree();
         }
     }
 
-    fn update_leaf<T: HashableData>(&mut self, index: usize, data: T) {
-        if let Some(node) = self.leaves.get(index) {
-            node.borrow_mut().value = self.hasher.get_hash_from_data(data);
-            self.rebuild_tree();
+    fn delete_leaf(&mut self, index: usize) -> Result<()> {
+        self.check_index(index)?;
+        if self.leaves.len() == 1 {
+            return Err(MerkleError::EmptyInput);
+        }
+        let leaf = self.leaves.remove(index);
+        if let Some(leaf_index) = &mut self.leaf_index {
+            leaf_index.delete(&self.arena[leaf].value, index);
         }
+        self.rebuild_tree();
+        Ok(())
     }
 
-    fn gen_proof(&self, leaf_idx: usize) -> Option<Vec<Hash>> {
-        let mut proof: Vec<Hash> = Vec::new();
-        let mut current_node = match self.leaves.get(leaf_idx) {
-            Some(node) => node.clone(),
-            None => return None,
-        };
+    fn update_leaf<T: HashableData>(&mut self, index: usize, data: T) -> Result<()> {
+        self.check_index(index)?;
+        let hash = self.get_leaf_hash(data);
+        if let Some(leaf_index) = &mut self.leaf_index {
+            leaf_index.remove(&self.arena[self.leaves[index]].value, index);
+            leaf_index.insert(&hash, index);
+        }
+        self.arena[self.leaves[index]].value = hash;
+        self.update_ancestors(index);
+        Ok(())
+    }
 
-        loop {
-            let sibling = current_node.borrow().get_sibling(0);
-            // this means we've reached the root node
-            if sibling.is_none() {
-                break;
-            }
-            proof.push(sibling.unwrap().borrow().value.clone());
-            // if it has a sibling, then it must have a parent
-            let parent_node = current_node.borrow().get_parent().unwrap();
-            current_node = parent_node;
+    fn gen_proof(&self, leaf_idx: usize) -> Result<MerkleProof> {
+        self.check_index(leaf_idx)?;
+        Ok(MerkleProof::new(
+            leaf_idx,
+            self.leaves.len(),
+            self.hasher.algorithm(),
+            self.options,
+            self.get_siblings(self.leaves[leaf_idx]),
+        ))
+    }
+
+    fn verify_proof(&self, leaf_hash: &Hash, proof: &MerkleProof) -> Result<()> {
+        if proof.options != self.options {
+            return Err(MerkleError::OptionsMismatch);
         }
+        proof.verify(&self.root_hash, leaf_hash, &self.hasher)
+    }
 
-        Some(proof)
+    fn gen_multi_proof(&self, leaf_indices: &[usize]) -> Result<MultiProof> {
+        MultiProof::generate(
+            leaf_indices,
+            self.leaves.len(),
+            self.hasher.algorithm(),
+            self.options,
+            |level, idx| {
+                let node = FullMerkleTree::<H>::get_node(
+                    &self.arena,
+                    &self.leaves,
+                    &self.options,
+                    level,
+                    idx,
+                );
+                self.arena[node].value.clone()
+            },
+        )
     }
 
-    fn verify_proof(&self, leaf_hash: &Hash, mut leaf_idx: usize, proof: Vec<Hash>) -> bool {
-        let mut leaf_hash = leaf_hash.clone();
-        for hash in proof {
-            if num::is_even(leaf_idx) {
-                leaf_hash = self.hasher.get_combined_hash(&leaf_hash, &hash);
-            } else {
-                leaf_hash = self.hasher.get_combined_hash(&hash, &leaf_hash);
-            }
-            leaf_idx /= 2;
+    fn verify_multi_proof(&self, leaf_hashes: &[Hash], proof: &MultiProof) -> Result<()> {
+        if proof.options != self.options {
+            return Err(MerkleError::OptionsMismatch);
         }
-        leaf_hash == self.root_hash
+        proof.verify(&self.root_hash, leaf_hashes, &self.hasher)
     }
 
-    fn contains_hash(&self, hash: &Hash) -> Option<(usize, Vec<Hash>)> {
-        let leaf = self
-            .leaves
-            .iter()
-            .enumerate()
-            .find(|(_, el)| el.borrow().value == *hash);
+    fn gen_consistency_proof(&self, old_size: usize, new_size: usize) -> Result<ConsistencyProof> {
+        if old_size == 0 || old_size > new_size || new_size > self.leaves.len() {
+            return Err(MerkleError::InvalidSizes {
+                old_size,
+                new_size,
+                len: self.leaves.len(),
+            });
+        }
+
+        let path = ConsistencyProof::gen_path(
+            old_size,
+            new_size,
+            &self.options,
+            &self.hasher,
+            |level, idx| {
+                let node = FullMerkleTree::<H>::get_node(
+                    &self.arena,
+                    &self.leaves,
+                    &self.options,
+                    level,
+                    idx,
+                );
+                self.arena[node].value.clone()
+            },
+        );
+
+        Ok(ConsistencyProof {
+            old_size,
+            new_size,
+            algorithm: self.hasher.algorithm(),
+            options: self.options,
+            last_leaf: self.arena[self.leaves[old_size - 1]].value.clone(),
+            path,
+        })
+    }
 
-        let leaf_idx = leaf?.0;
+    fn contains_hash(&self, hash: &Hash) -> Option<(usize, MerkleProof)> {
+        let leaf_idx = self.find_leaf(hash)?;
         // if the leaf exists then the gen_proof also does
-        return Some((leaf_idx, self.gen_proof(leaf_idx).unwrap()));
+        Some((leaf_idx, self.gen_proof(leaf_idx).unwrap()))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<H: Hasher> serde::Serialize for FullMerkleTree<H> {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        self.snapshot().serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, H: Hasher + Default> serde::Deserialize<'de> for FullMerkleTree<H> {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        let snapshot = TreeSnapshot::deserialize(deserializer)?;
+        FullMerkleTree::from_snapshot(snapshot, H::default()).map_err(serde::de::Error::custom)
     }
 }
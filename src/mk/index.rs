@@ -0,0 +1,50 @@
+use std::collections::HashMap;
+
+use crate::utils::crypto::Hash;
+
+/// Positions of every leaf by hash, so lookups don't have to scan the leaves.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct LeafIndex {
+    /// Positions are kept sorted, duplicate leaves sharing the same entry.
+    positions: HashMap<Hash, Vec<usize>>,
+}
+
+impl LeafIndex {
+    pub(crate) fn from_hashes<'a>(hashes: impl IntoIterator<Item = &'a Hash>) -> Self {
+        let mut index = LeafIndex::default();
+        for (idx, hash) in hashes.into_iter().enumerate() {
+            index.insert(hash, idx);
+        }
+        index
+    }
+
+    pub(crate) fn get(&self, hash: &Hash) -> &[usize] {
+        self.positions.get(hash).map_or(&[], |positions| positions)
+    }
+
+    pub(crate) fn insert(&mut self, hash: &Hash, idx: usize) {
+        let positions = self.positions.entry(hash.clone()).or_default();
+        if let Err(at) = positions.binary_search(&idx) {
+            positions.insert(at, idx);
+        }
+    }
+
+    pub(crate) fn remove(&mut self, hash: &Hash, idx: usize) {
+        if let Some(positions) = self.positions.get_mut(hash) {
+            positions.retain(|position| *position != idx);
+            if positions.is_empty() {
+                self.positions.remove(hash);
+            }
+        }
+    }
+
+    /// Removes the leaf at `idx`, moving the ones after it a position to the left.
+    pub(crate) fn delete(&mut self, hash: &Hash, idx: usize) {
+        self.remove(hash, idx);
+        for positions in self.positions.values_mut() {
+            for position in positions.iter_mut().filter(|position| **position > idx) {
+                *position -= 1;
+            }
+        }
+    }
+}
@@ -1,5 +1,8 @@
+use crate::error::Result;
 use crate::utils::crypto::{Hash, HashableData};
 
+use super::{consistency::ConsistencyProof, multiproof::MultiProof, proof::MerkleProof};
+
 /// A trait defining operations for a Merkle tree.
 pub trait MerkleTree<MKNode> {
     /// Retrieves a leaf node by its index in the Merkle tree.
@@ -12,88 +15,171 @@ pub trait MerkleTree<MKNode> {
     /// Returns `Some(node)` if a leaf exists with the specified `hash`, otherwise `None`.
     fn get_leaf_by_hash(&self, hash: &Hash) -> Option<MKNode>;
 
-    /// Adds a new leaf node to the Merkle tree. This will rebuild the tree
+    /// Retrieves the positions of every leaf with the specified `hash`, in ascending order.
+    ///
+    /// Lookups scan the leaves unless the tree keeps an index of them, see `enable_leaf_index`
+    /// on each tree.
     ///
     /// # Examples
     ///
     /// ```
-    /// use my_crate::MerkleTree;
+    /// use merkle_tree::mk::compact::CompactMerkleTree;
+    /// use merkle_tree::mk::mk::MerkleTree;
+    /// use merkle_tree::utils::crypto::Sha256Hasher;
+    ///
+    /// let data = ["hello", "world", "hello"];
+    /// let mut tree = CompactMerkleTree::create(&data, Sha256Hasher::new()).unwrap();
+    /// tree.enable_leaf_index();
+    /// assert_eq!(tree.get_leaf_indices(&tree.get_leaf_hash("hello")), vec![0, 2]);
+    /// ```
+    fn get_leaf_indices(&self, hash: &Hash) -> Vec<usize>;
+
+    /// Adds a new leaf node to the Merkle tree, rehashing only the path from it to the root.
+    /// `FullMerkleTree` also rebuilds itself once the nodes replaced this way double its size.
     ///
-    /// let mut tree = create_merkle_tree(); // Assuming a function to create a Merkle tree
+    /// # Examples
+    ///
+    /// ```
+    /// use merkle_tree::mk::compact::CompactMerkleTree;
+    /// use merkle_tree::mk::mk::MerkleTree;
+    /// use merkle_tree::utils::crypto::Sha256Hasher;
+    ///
+    /// let mut tree = CompactMerkleTree::create(&["hello", "world"], Sha256Hasher::new()).unwrap();
     /// tree.add_leaf("new_data");
     /// ```
     fn add_leaf<T: HashableData>(&mut self, data: T);
 
-    /// Deletes a leaf node from the Merkle tree by its index. This will rebuild the tree
+    /// Deletes a leaf node from the Merkle tree by its index. Every leaf after it moves one
+    /// position to the left, so the tree rehashes the ancestors of the leaves from `index`
+    /// onward, which is the whole tree for `FullMerkleTree`.
+    ///
+    /// Fails with `MerkleError::IndexOutOfRange` if there is no leaf at `index`, or with
+    /// `MerkleError::EmptyInput` if it is the only one left.
     ///
     /// # Examples
     ///
     /// ```
-    /// use my_crate::MerkleTree;
+    /// use merkle_tree::mk::compact::CompactMerkleTree;
+    /// use merkle_tree::mk::mk::MerkleTree;
+    /// use merkle_tree::utils::crypto::Sha256Hasher;
     ///
-    /// let mut tree = create_merkle_tree(); // Assuming a function to create a Merkle tree
-    /// tree.delete_leaf(0);
+    /// let mut tree = CompactMerkleTree::create(&["hello", "world"], Sha256Hasher::new()).unwrap();
+    /// tree.delete_leaf(0).unwrap();
+    /// assert!(tree.delete_leaf(5).is_err());
     /// ```
-    fn delete_leaf(&mut self, index: usize);
+    fn delete_leaf(&mut self, index: usize) -> Result<()>;
 
-    /// Updates a leaf node in the Merkle tree by its index. This will rebuild the tree
+    /// Updates a leaf node in the Merkle tree by its index, rehashing only the path from it to
+    /// the root.
+    ///
+    /// Fails with `MerkleError::IndexOutOfRange` if there is no leaf at `index`.
     ///
     /// # Examples
     ///
     /// ```
-    /// use my_crate::MerkleTree;
+    /// use merkle_tree::mk::compact::CompactMerkleTree;
+    /// use merkle_tree::mk::mk::MerkleTree;
+    /// use merkle_tree::utils::crypto::Sha256Hasher;
     ///
-    /// let mut tree = create_merkle_tree(); // Assuming a function to create a Merkle tree
-    /// tree.update_leaf(0, "updated_data");
+    /// let mut tree = CompactMerkleTree::create(&["hello", "world"], Sha256Hasher::new()).unwrap();
+    /// tree.update_leaf(0, "updated_data").unwrap();
     /// ```
-    fn update_leaf<T: HashableData>(&mut self, index: usize, data: T);
+    fn update_leaf<T: HashableData>(&mut self, index: usize, data: T) -> Result<()>;
 
     /// Generates a Merkle proof for a leaf node at the specified index.
     ///
-    /// Returns `Some(proof)` if a leaf exists at the specified `leaf_idx`, otherwise `None`.
+    /// Fails with `MerkleError::IndexOutOfRange` if there is no leaf at `leaf_idx`.
     ///
     /// # Examples
     ///
     /// ```
-    /// use my_crate::MerkleTree;
+    /// use merkle_tree::mk::compact::CompactMerkleTree;
+    /// use merkle_tree::mk::mk::MerkleTree;
+    /// use merkle_tree::utils::crypto::Sha256Hasher;
     ///
-    /// let tree = create_merkle_tree(); // Assuming a function to create a Merkle tree
-    /// if let Some(proof) = tree.gen_proof(0) {
+    /// let tree = CompactMerkleTree::create(&["hello", "world"], Sha256Hasher::new()).unwrap();
+    /// if let Ok(proof) = tree.gen_proof(0) {
     ///     println!("Merkle Proof: {:?}", proof);
     /// }
     /// ```
-    fn gen_proof(&self, leaf_idx: usize) -> Option<Vec<Hash>>;
+    fn gen_proof(&self, leaf_idx: usize) -> Result<MerkleProof>;
 
     /// Verifies a Merkle proof for a leaf node.
     ///
-    /// Returns `true` if the proof is valid for the leaf node with `leaf_hash` and `leaf_idx`, otherwise `false`.
+    /// Returns `Ok(())` if the proof is valid for the leaf node with `leaf_hash` at `proof.leaf_idx`,
+    /// otherwise an error telling why it isn't.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use merkle_tree::mk::compact::CompactMerkleTree;
+    /// use merkle_tree::mk::mk::MerkleTree;
+    /// use merkle_tree::utils::crypto::Sha256Hasher;
+    ///
+    /// let tree = CompactMerkleTree::create(&["hello", "world"], Sha256Hasher::new()).unwrap();
+    /// let leaf_hash = tree.get_leaf_hash("hello");
+    /// let proof = tree.gen_proof(0).unwrap();
+    /// assert!(tree.verify_proof(&leaf_hash, &proof).is_ok());
+    /// ```
+    fn verify_proof(&self, leaf_hash: &Hash, proof: &MerkleProof) -> Result<()>;
+
+    /// Generates a single proof for all the leaves at `leaf_indices`.
+    ///
+    /// Fails if there are no indices or any of them doesn't point to an existing leaf.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use merkle_tree::mk::compact::CompactMerkleTree;
+    /// use merkle_tree::mk::mk::MerkleTree;
+    /// use merkle_tree::utils::crypto::Sha256Hasher;
+    ///
+    /// let tree = CompactMerkleTree::create(&["hello", "how", "are", "you"], Sha256Hasher::new()).unwrap();
+    /// let proof = tree.gen_multi_proof(&[0, 1, 3]).unwrap();
+    /// // only the sibling of the last leaf is needed, the rest is computed by the verifier
+    /// assert_eq!(proof.siblings.len(), 1);
+    /// ```
+    fn gen_multi_proof(&self, leaf_indices: &[usize]) -> Result<MultiProof>;
+
+    /// Verifies a multiproof, `leaf_hashes` being given in the order of `proof.leaf_indices`.
+    ///
+    /// Returns `Ok(())` if the proof is valid for all the leaves, otherwise an error telling why it isn't.
+    fn verify_multi_proof(&self, leaf_hashes: &[Hash], proof: &MultiProof) -> Result<()>;
+
+    /// Generates a proof that the tree made of the first `old_size` leaves is a prefix of the
+    /// one made of the first `new_size` leaves. Check it with `ConsistencyProof::verify`.
+    ///
+    /// Fails with `MerkleError::InvalidSizes` unless `0 < old_size <= new_size <= number of leaves`.
     ///
     /// # Examples
     ///
     /// ```
-    /// use my_crate::MerkleTree;
+    /// use merkle_tree::mk::compact::CompactMerkleTree;
+    /// use merkle_tree::mk::mk::MerkleTree;
+    /// use merkle_tree::utils::crypto::Sha256Hasher;
     ///
-    /// let tree = create_merkle_tree(); // Assuming a function to create a Merkle tree
-    /// let leaf_hash = get_hash("data_to_verify");
-    /// let proof = vec![get_hash("sibling_hash"), get_hash("parent_hash")];
-    /// assert!(tree.verify_proof(&leaf_hash, 0, proof));
+    /// let tree = CompactMerkleTree::create(&["hello", "how", "are", "you"], Sha256Hasher::new()).unwrap();
+    /// assert!(tree.gen_consistency_proof(2, 4).is_ok());
+    /// assert!(tree.gen_consistency_proof(2, 5).is_err());
     /// ```
-    fn verify_proof(&self, leaf_hash: &Hash, leaf_idx: usize, proof: Vec<Hash>) -> bool;
+    fn gen_consistency_proof(&self, old_size: usize, new_size: usize) -> Result<ConsistencyProof>;
 
     /// Checks if a hash exists as a leaf node in the Merkle tree.
     ///
-    /// Returns `Some((idx, proof))` if the `hash` exists as a leaf node, otherwise `None`.
+    /// Returns `Some((idx, proof))` for the first leaf with the `hash`, otherwise `None`.
     ///
     /// # Examples
     ///
     /// ```
-    /// use my_crate::MerkleTree;
+    /// use merkle_tree::mk::compact::CompactMerkleTree;
+    /// use merkle_tree::mk::mk::MerkleTree;
+    /// use merkle_tree::utils::crypto::Sha256Hasher;
     ///
-    /// let tree = create_merkle_tree(); // Assuming a function to create a Merkle tree
-    /// let hash_to_find = get_hash("data_to_find");
+    /// let tree = CompactMerkleTree::create(&["hello", "world"], Sha256Hasher::new()).unwrap();
+    /// let hash_to_find = tree.get_leaf_hash("world");
     /// if let Some((idx, proof)) = tree.contains_hash(&hash_to_find) {
     ///     println!("Found at index {}: {:?}", idx, proof);
     /// }
     /// ```
-    fn contains_hash(&self, hash: &Hash) -> Option<(usize, Vec<Hash>)>;
+    fn contains_hash(&self, hash: &Hash) -> Option<(usize, MerkleProof)>;
 }
@@ -0,0 +1,263 @@
+use crate::error::{MerkleError, Result};
+use crate::utils::{crypto::*, num::is_even};
+
+use super::proof::{check_algorithm, check_hash_lengths, check_hashing_mode};
+
+/// The height and first leaf of every mountain of a range with `size` leaves, left to right.
+fn get_mountains(size: usize) -> Vec<(usize, usize)> {
+    let mut mountains = Vec::new();
+    let mut start = 0;
+    for height in (0..usize::BITS as usize).rev() {
+        if size & (1 << height) != 0 {
+            mountains.push((height, start));
+            start += 1 << height;
+        }
+    }
+    mountains
+}
+
+/// The position of the mountain holding `leaf_idx` among the ones of `size`, and its height.
+fn find_mountain(leaf_idx: usize, size: usize) -> (usize, usize) {
+    get_mountains(size)
+        .into_iter()
+        .enumerate()
+        .find(|(_, (height, start))| leaf_idx < start + (1 << height))
+        .map(|(peak_idx, (height, _))| (peak_idx, height))
+        .unwrap()
+}
+
+/// Hashes the peaks into a single root, from the rightmost one to the left.
+fn bag_peaks<H: Hasher>(peaks: &[Hash], hasher: &H, hashing_mode: HashingMode) -> Option<Hash> {
+    let (last, rest) = peaks.split_last()?;
+    Some(rest.iter().rev().fold(last.clone(), |root, peak| {
+        hasher.get_node_hash(peak, &root, hashing_mode)
+    }))
+}
+
+/// An append-only accumulator made of perfect binary trees ("mountains"), one for every bit
+/// set in the number of leaves.
+///
+/// Appending only hashes the mountains it merges, and nodes are never rewritten, so the root
+/// and proofs of any former size can still be produced.
+#[derive(Clone)]
+pub struct MerkleMountainRange<H: Hasher> {
+    pub hasher: H,
+    pub hashing_mode: HashingMode,
+    /// Every node by height, `levels[0]` being the leaves.
+    levels: Vec<Vec<Hash>>,
+}
+
+impl<H: Hasher> MerkleMountainRange<H> {
+    pub fn new(hasher: H) -> Self {
+        MerkleMountainRange::new_empty(hasher, HashingMode::default())
+    }
+
+    /// Creates an empty range hashed with `Plain` or `DomainSeparated`; `SortedPair` is
+    /// rejected, as it would let a proof pass for any leaf of the same mountain.
+    pub fn new_with_hashing_mode(hasher: H, hashing_mode: HashingMode) -> Result<Self> {
+        check_hashing_mode(hashing_mode)?;
+        Ok(MerkleMountainRange::new_empty(hasher, hashing_mode))
+    }
+
+    fn new_empty(hasher: H, hashing_mode: HashingMode) -> Self {
+        Self {
+            hasher,
+            hashing_mode,
+            levels: vec![Vec::new()],
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.levels[0].len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.levels[0].is_empty()
+    }
+
+    /// Hashes `data` the way this range hashes its leaves.
+    pub fn get_leaf_hash<T: HashableData>(&self, data: T) -> Hash {
+        self.hasher.get_leaf_hash(data, self.hashing_mode)
+    }
+
+    pub fn get_leaf(&self, idx: usize) -> Option<&Hash> {
+        self.levels[0].get(idx)
+    }
+
+    /// Appends a leaf, merging the mountains of its height, and returns its index.
+    pub fn append<T: HashableData>(&mut self, data: T) -> usize {
+        let leaf = self.get_leaf_hash(data);
+        self.levels[0].push(leaf);
+
+        let mut height = 0;
+        while is_even(self.levels[height].len()) {
+            let nodes = &self.levels[height];
+            let parent = self.hasher.get_node_hash(
+                &nodes[nodes.len() - 2],
+                &nodes[nodes.len() - 1],
+                self.hashing_mode,
+            );
+            height += 1;
+            if self.levels.len() == height {
+                self.levels.push(Vec::new());
+            }
+            self.levels[height].push(parent);
+        }
+
+        self.len() - 1
+    }
+
+    /// The roots of the mountains when the range had `size` leaves, left to right.
+    pub fn get_peaks_at(&self, size: usize) -> Result<Vec<Hash>> {
+        self.check_size(size)?;
+        Ok(get_mountains(size)
+            .into_iter()
+            .map(|(height, start)| self.levels[height][start >> height].clone())
+            .collect())
+    }
+
+    /// The current root, or `None` if nothing has been appended yet.
+    pub fn root_hash(&self) -> Option<Hash> {
+        self.get_root_at(self.len()).ok()
+    }
+
+    /// The root the range had with `size` leaves.
+    pub fn get_root_at(&self, size: usize) -> Result<Hash> {
+        if size == 0 {
+            return Err(MerkleError::EmptyInput);
+        }
+        let peaks = self.get_peaks_at(size)?;
+        Ok(bag_peaks(&peaks, &self.hasher, self.hashing_mode).unwrap())
+    }
+
+    /// Generates an inclusion proof for the leaf at `leaf_idx` against the current root.
+    pub fn gen_proof(&self, leaf_idx: usize) -> Result<MmrProof> {
+        self.gen_proof_at(leaf_idx, self.len())
+    }
+
+    /// Generates an inclusion proof for the leaf at `leaf_idx` against the root the range had
+    /// with `size` leaves.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use merkle_tree::mk::mmr::MerkleMountainRange;
+    /// use merkle_tree::utils::crypto::Sha256Hasher;
+    ///
+    /// let mut mmr = MerkleMountainRange::new(Sha256Hasher::new());
+    /// for event in ["created", "updated", "deleted"] {
+    ///     mmr.append(event);
+    /// }
+    /// let old_root = mmr.root_hash().unwrap();
+    /// let proof = mmr.gen_proof(1).unwrap();
+    ///
+    /// mmr.append("created");
+    /// let leaf_hash = mmr.get_leaf_hash("updated");
+    /// assert!(proof.verify(&old_root, &leaf_hash, &mmr.hasher).is_ok());
+    /// assert_eq!(mmr.gen_proof_at(1, 3).unwrap(), proof);
+    /// ```
+    pub fn gen_proof_at(&self, leaf_idx: usize, size: usize) -> Result<MmrProof> {
+        self.check_size(size)?;
+        if leaf_idx >= size {
+            return Err(MerkleError::IndexOutOfRange {
+                index: leaf_idx,
+                len: size,
+            });
+        }
+
+        let mut peaks = self.get_peaks_at(size)?;
+        let (peak_idx, height) = find_mountain(leaf_idx, size);
+        peaks.remove(peak_idx);
+
+        let path = (0..height)
+            .map(|level| self.levels[level][(leaf_idx >> level) ^ 1].clone())
+            .collect();
+
+        Ok(MmrProof {
+            leaf_idx,
+            size,
+            algorithm: self.hasher.algorithm(),
+            hashing_mode: self.hashing_mode,
+            path,
+            peaks,
+        })
+    }
+
+    fn check_size(&self, size: usize) -> Result<()> {
+        if size > self.len() {
+            return Err(MerkleError::IndexOutOfRange {
+                index: size,
+                len: self.len(),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Proves that a leaf is in a `MerkleMountainRange` of `size` leaves.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MmrProof {
+    pub leaf_idx: usize,
+    pub size: usize,
+    pub algorithm: HashAlgorithm,
+    pub hashing_mode: HashingMode,
+    /// Siblings up to the peak of the mountain holding the leaf, from the leaf level up.
+    pub path: Vec<Hash>,
+    /// The peaks of every other mountain, left to right.
+    pub peaks: Vec<Hash>,
+}
+
+impl MmrProof {
+    /// Recomputes the root from `leaf_hash`, failing if the proof doesn't fit `size`.
+    pub fn compute_root<H: Hasher>(&self, leaf_hash: &Hash, hasher: &H) -> Result<Hash> {
+        check_algorithm(hasher, self.algorithm)?;
+        check_hashing_mode(self.hashing_mode)?;
+        if self.leaf_idx >= self.size {
+            return Err(MerkleError::IndexOutOfRange {
+                index: self.leaf_idx,
+                len: self.size,
+            });
+        }
+
+        let (peak_idx, height) = find_mountain(self.leaf_idx, self.size);
+        if self.path.len() != height {
+            return Err(MerkleError::ProofLengthMismatch {
+                expected: height,
+                actual: self.path.len(),
+            });
+        }
+        let other_peaks = self.size.count_ones() as usize - 1;
+        if self.peaks.len() != other_peaks {
+            return Err(MerkleError::ProofLengthMismatch {
+                expected: other_peaks,
+                actual: self.peaks.len(),
+            });
+        }
+        check_hash_lengths(leaf_hash.len(), self.path.iter().chain(&self.peaks))?;
+
+        let peak =
+            self.path
+                .iter()
+                .enumerate()
+                .fold(leaf_hash.clone(), |hash, (level, sibling)| {
+                    match is_even(self.leaf_idx >> level) {
+                        true => hasher.get_node_hash(&hash, sibling, self.hashing_mode),
+                        false => hasher.get_node_hash(sibling, &hash, self.hashing_mode),
+                    }
+                });
+        let mut peaks = self.peaks.clone();
+        peaks.insert(peak_idx, peak);
+
+        Ok(bag_peaks(&peaks, hasher, self.hashing_mode).unwrap())
+    }
+
+    /// Verifies the proof against the `root` of the range when it had `size` leaves.
+    pub fn verify<H: Hasher>(&self, root: &Hash, leaf_hash: &Hash, hasher: &H) -> Result<()> {
+        let computed = self.compute_root(leaf_hash, hasher)?;
+        check_hash_lengths(leaf_hash.len(), [root])?;
+        if computed != *root {
+            return Err(MerkleError::RootMismatch);
+        }
+        Ok(())
+    }
+}
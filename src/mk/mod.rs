@@ -1,3 +1,19 @@
 pub mod compact;
+pub mod consistency;
+pub mod frontier;
 pub mod full;
+mod index;
+#[allow(clippy::module_inception)]
 pub mod mk;
+pub mod mmr;
+pub mod multiproof;
+pub mod options;
+pub mod proof;
+pub mod rfc6962;
+pub mod shared;
+pub mod snapshot;
+pub mod sorted;
+pub mod sparse;
+pub mod standard;
+pub mod sum;
+pub mod wire;
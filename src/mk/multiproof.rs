@@ -0,0 +1,213 @@
+use crate::error::{MerkleError, Result};
+use crate::utils::crypto::*;
+
+use super::{
+    options::TreeOptions,
+    proof::{check_algorithm, check_hash_lengths},
+};
+
+/// A single proof for several leaves of the same tree.
+///
+/// Siblings shared by more than one leaf and nodes that can be computed from the proven
+/// leaves themselves are only included once (or not at all), so the proof is much smaller
+/// than one `MerkleProof` per leaf.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MultiProof {
+    /// Indices of the proven leaves, sorted and without duplicates.
+    pub leaf_indices: Vec<usize>,
+    pub tree_size: usize,
+    pub algorithm: HashAlgorithm,
+    pub options: TreeOptions,
+    /// Siblings in the order the verifier consumes them: level by level from the leaves up,
+    /// left to right within a level.
+    pub siblings: Vec<Hash>,
+}
+
+impl MultiProof {
+    /// Builds a proof for `leaf_indices` by asking `get_node(level, idx)` for every sibling the
+    /// verifier won't be able to compute by itself.
+    pub(crate) fn generate<F>(
+        leaf_indices: &[usize],
+        tree_size: usize,
+        algorithm: HashAlgorithm,
+        options: TreeOptions,
+        get_node: F,
+    ) -> Result<Self>
+    where
+        F: Fn(usize, usize) -> Hash,
+    {
+        let mut leaf_indices = leaf_indices.to_vec();
+        leaf_indices.sort_unstable();
+        leaf_indices.dedup();
+
+        match leaf_indices.last() {
+            None => return Err(MerkleError::InvalidIndices),
+            Some(&index) if index >= tree_size => {
+                return Err(MerkleError::IndexOutOfRange {
+                    index,
+                    len: tree_size,
+                })
+            }
+            _ => {}
+        }
+
+        let mut siblings = Vec::new();
+        MultiProof::walk_missing(&leaf_indices, tree_size, &options, |level, idx| {
+            siblings.push(get_node(level, idx))
+        });
+
+        Ok(Self {
+            leaf_indices,
+            tree_size,
+            algorithm,
+            options,
+            siblings,
+        })
+    }
+
+    /// Goes up the levels the verifier has to, calling `on_missing(level, idx)` for every
+    /// sibling it can't compute from the proven leaves.
+    fn walk_missing<F>(
+        leaf_indices: &[usize],
+        tree_size: usize,
+        options: &TreeOptions,
+        mut on_missing: F,
+    ) where
+        F: FnMut(usize, usize),
+    {
+        let mut known = leaf_indices.to_vec();
+        let mut level_size = tree_size;
+        let mut level = 0;
+
+        while level_size > 1 {
+            let mut parents = Vec::new();
+            let mut known_iter = known.iter().peekable();
+            while let Some(&idx) = known_iter.next() {
+                for sibling_idx in options.get_group(idx, level_size) {
+                    if sibling_idx < idx {
+                        on_missing(level, sibling_idx);
+                    } else if sibling_idx > idx {
+                        match known_iter.peek() {
+                            Some(&&next) if next == sibling_idx => {
+                                known_iter.next();
+                            }
+                            _ => on_missing(level, sibling_idx),
+                        }
+                    }
+                }
+                parents.push(idx / options.branching_factor);
+            }
+            known = parents;
+            level_size = options.get_parent_level_size(level_size);
+            level += 1;
+        }
+    }
+
+    /// Recomputes the root from the hashes of the proven leaves, given in the same order as
+    /// `leaf_indices`.
+    ///
+    /// Fails if the proof is malformed, that is, the number of leaves or siblings doesn't
+    /// match what the tree shape requires.
+    pub fn compute_root<H: Hasher>(&self, leaf_hashes: &[Hash], hasher: &H) -> Result<Hash> {
+        if self.leaf_indices.is_empty() || self.leaf_indices.windows(2).any(|w| w[0] >= w[1]) {
+            return Err(MerkleError::InvalidIndices);
+        }
+        let last_idx = *self.leaf_indices.last().unwrap();
+        if last_idx >= self.tree_size {
+            return Err(MerkleError::IndexOutOfRange {
+                index: last_idx,
+                len: self.tree_size,
+            });
+        }
+        if leaf_hashes.len() != self.leaf_indices.len() {
+            return Err(MerkleError::LeafCountMismatch {
+                expected: self.leaf_indices.len(),
+                actual: leaf_hashes.len(),
+            });
+        }
+
+        self.options.validate()?;
+        let mut expected = 0;
+        MultiProof::walk_missing(&self.leaf_indices, self.tree_size, &self.options, |_, _| {
+            expected += 1
+        });
+        if expected != self.siblings.len() {
+            return Err(MerkleError::ProofLengthMismatch {
+                expected,
+                actual: self.siblings.len(),
+            });
+        }
+        check_hash_lengths(
+            leaf_hashes[0].len(),
+            leaf_hashes.iter().chain(&self.siblings),
+        )?;
+
+        let mut siblings = self.siblings.iter();
+        let mut known: Vec<(usize, Hash)> = self
+            .leaf_indices
+            .iter()
+            .copied()
+            .zip(leaf_hashes.iter().cloned())
+            .collect();
+        let mut level_size = self.tree_size;
+        let mut level = 0;
+
+        while level_size > 1 {
+            let mut parents = Vec::new();
+            let mut known_iter = known.into_iter().peekable();
+            while let Some((idx, hash)) = known_iter.next() {
+                let mut children = Vec::new();
+                for sibling_idx in self.options.get_group(idx, level_size) {
+                    if sibling_idx == idx {
+                        children.push(hash.clone());
+                        continue;
+                    }
+                    match known_iter.peek() {
+                        Some((next, _)) if sibling_idx > idx && *next == sibling_idx => {
+                            children.push(known_iter.next().unwrap().1);
+                        }
+                        // the number of siblings was checked against the shape above
+                        _ => children.push(siblings.next().unwrap().clone()),
+                    }
+                }
+                let parent = self.options.get_parent(&children, level, hasher);
+                parents.push((idx / self.options.branching_factor, parent));
+            }
+            known = parents;
+            level_size = self.options.get_parent_level_size(level_size);
+            level += 1;
+        }
+
+        Ok(known.pop().unwrap().1)
+    }
+
+    /// Verifies the proof against a known `root` by recomputing it once for all leaves.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use merkle_tree::mk::compact::CompactMerkleTree;
+    /// use merkle_tree::mk::mk::MerkleTree;
+    /// use merkle_tree::utils::crypto::Sha256Hasher;
+    ///
+    /// let data = vec!["hello", "how", "are", "you", "doing"];
+    /// let tree = CompactMerkleTree::create(&data, Sha256Hasher::new()).unwrap();
+    /// let proof = tree.gen_multi_proof(&[0, 1, 4]).unwrap();
+    ///
+    /// let hasher = Sha256Hasher::new();
+    /// let leaves = vec![
+    ///     tree.get_leaf_hash("hello"),
+    ///     tree.get_leaf_hash("how"),
+    ///     tree.get_leaf_hash("doing"),
+    /// ];
+    /// assert!(proof.verify(&tree.root_hash, &leaves, &hasher).is_ok());
+    /// ```
+    pub fn verify<H: Hasher>(&self, root: &Hash, leaf_hashes: &[Hash], hasher: &H) -> Result<()> {
+        check_algorithm(hasher, self.algorithm)?;
+
+        if self.compute_root(leaf_hashes, hasher)? != *root {
+            return Err(MerkleError::RootMismatch);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,162 @@
+use std::ops::Range;
+
+use crate::error::{MerkleError, Result};
+use crate::utils::crypto::{Hash, Hasher, HashingMode};
+
+/// What to do with the last nodes of a level when they can't fill a whole group of children.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OddNodePolicy {
+    /// Repeat the last node until the group is full, as Bitcoin does.
+    ///
+    /// Lists like `[a, b, c]` and `[a, b, c, c]` end up with the same root (CVE-2012-2459),
+    /// so don't rely on the root alone to tell two lists apart.
+    #[default]
+    Duplicate,
+    /// Hash the group with the nodes it has, or move a node alone up to the next level
+    /// unchanged, as RFC 6962 does.
+    Promote,
+    /// Pad the leaves with zero hashes up to the next power of the branching factor.
+    PadToPowerOfTwo,
+}
+
+/// Settings that change how a tree is built, and therefore its root.
+///
+/// Proofs carry the options of the tree they were generated from, so they can be checked
+/// without it. When verifying a proof from an untrusted party, make sure its options are the
+/// ones you expect.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TreeOptions {
+    pub hashing_mode: HashingMode,
+    pub odd_node_policy: OddNodePolicy,
+    /// Number of children of every interior node, at least 2. Wider trees are shallower, but
+    /// their proofs carry `branching_factor - 1` siblings per level.
+    pub branching_factor: usize,
+}
+
+impl Default for TreeOptions {
+    fn default() -> Self {
+        Self {
+            hashing_mode: HashingMode::default(),
+            odd_node_policy: OddNodePolicy::default(),
+            branching_factor: 2,
+        }
+    }
+}
+
+impl TreeOptions {
+    /// The options of OpenZeppelin's `StandardMerkleTree`, to be used with a
+    /// `Keccak256Hasher`: sorted pair hashing, double hashed leaves and lone nodes promoted.
+    ///
+    /// Proofs from any tree built this way pass `MerkleProof.verify` on-chain. The roots match
+    /// the ones of `StandardMerkleTree` only when the leaves are sorted by hash, as
+    /// `SortedMerkleTree` does, and there are a power of two of them or three times one, as
+    /// other sizes pair their last nodes differently. Use `mk::standard::StandardMerkleTree`
+    /// to get the same root for any size.
+    pub fn openzeppelin() -> Self {
+        Self {
+            hashing_mode: HashingMode::SortedPair,
+            odd_node_policy: OddNodePolicy::Promote,
+            branching_factor: 2,
+        }
+    }
+
+    /// The options of Bitcoin's transaction trees, to be used with a `DoubleSha256Hasher`:
+    /// no domain separation and the last node of odd levels duplicated.
+    pub fn bitcoin() -> Self {
+        Self {
+            hashing_mode: HashingMode::Plain,
+            odd_node_policy: OddNodePolicy::Duplicate,
+            branching_factor: 2,
+        }
+    }
+
+    /// The options of RFC 6962 trees: domain separation and lone nodes promoted, which gives
+    /// the same roots as splitting at the largest power of two. See `Rfc6962MerkleTree` for
+    /// the audit paths and consistency proofs of the RFC.
+    pub fn rfc6962() -> Self {
+        Self {
+            hashing_mode: HashingMode::DomainSeparated,
+            odd_node_policy: OddNodePolicy::Promote,
+            branching_factor: 2,
+        }
+    }
+
+    /// Checks that a tree can be built with these options.
+    pub(crate) fn validate(&self) -> Result<()> {
+        if self.branching_factor < 2 {
+            return Err(MerkleError::InvalidBranchingFactor {
+                branching_factor: self.branching_factor,
+            });
+        }
+        Ok(())
+    }
+
+    /// The number of parents of a level of `level_size` nodes.
+    pub(crate) fn get_parent_level_size(&self, level_size: usize) -> usize {
+        level_size.div_ceil(self.branching_factor)
+    }
+
+    /// The nodes of a level of `level_size` nodes sharing a parent with the one at `idx`.
+    pub(crate) fn get_group(&self, idx: usize, level_size: usize) -> Range<usize> {
+        let start = idx - idx % self.branching_factor;
+        start..level_size.min(start + self.branching_factor)
+    }
+
+    /// Whether the node at `idx` is the last of a level of `level_size` nodes and has no sibling.
+    pub(crate) fn is_lone(&self, idx: usize, level_size: usize) -> bool {
+        level_size > 1 && idx + 1 == level_size && idx.is_multiple_of(self.branching_factor)
+    }
+
+    /// Whether the node at `idx` moves up a level as is.
+    pub(crate) fn is_promoted(&self, idx: usize, level_size: usize) -> bool {
+        self.odd_node_policy == OddNodePolicy::Promote && self.is_lone(idx, level_size)
+    }
+
+    /// The hash of a subtree made only of padding, `level` levels above the leaves.
+    pub(crate) fn get_zero_hash<H: Hasher>(&self, level: usize, len: usize, hasher: &H) -> Hash {
+        (0..level).fold(vec![0; len], |zero, _| {
+            hasher.get_children_hash(&vec![zero; self.branching_factor], self.hashing_mode)
+        })
+    }
+
+    /// The nodes a group at `level` ending with `last` is completed with when it only has
+    /// `len` of them.
+    pub(crate) fn get_padding<H: Hasher>(
+        &self,
+        last: &Hash,
+        len: usize,
+        level: usize,
+        hasher: &H,
+    ) -> Vec<Hash> {
+        let missing = self.branching_factor - len;
+        match self.odd_node_policy {
+            OddNodePolicy::Duplicate => vec![last.clone(); missing],
+            OddNodePolicy::Promote => Vec::new(),
+            OddNodePolicy::PadToPowerOfTwo => {
+                vec![self.get_zero_hash(level, last.len(), hasher); missing]
+            }
+        }
+    }
+
+    /// The parent of the group of `children` at `level`, which may not be full.
+    pub(crate) fn get_parent<H: Hasher>(
+        &self,
+        children: &[Hash],
+        level: usize,
+        hasher: &H,
+    ) -> Hash {
+        let last = children.last().unwrap();
+        if children.len() == 1 && self.odd_node_policy == OddNodePolicy::Promote {
+            return last.clone();
+        }
+
+        let padding = self.get_padding(last, children.len(), level, hasher);
+        if padding.is_empty() {
+            return hasher.get_children_hash(children, self.hashing_mode);
+        }
+        let children = [children, &padding].concat();
+        hasher.get_children_hash(&children, self.hashing_mode)
+    }
+}
@@ -0,0 +1,212 @@
+use crate::error::{MerkleError, Result};
+use crate::utils::crypto::*;
+
+use super::options::{OddNodePolicy, TreeOptions};
+
+/// One level of the path from a leaf to the root.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProofNode {
+    /// Position of the node being hashed up the path among the children of its parent.
+    pub position: usize,
+    /// The other children of the parent, left to right.
+    pub siblings: Vec<Hash>,
+}
+
+/// An inclusion proof for a single leaf.
+///
+/// The proof carries everything needed to recompute the root, so it can be checked
+/// by someone who knows nothing more than the expected root hash.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MerkleProof {
+    pub leaf_idx: usize,
+    pub tree_size: usize,
+    pub algorithm: HashAlgorithm,
+    pub options: TreeOptions,
+    /// Levels ordered from the leaf up to the one right below the root.
+    pub path: Vec<ProofNode>,
+}
+
+impl MerkleProof {
+    /// Builds a proof for the leaf at `leaf_idx` from all its `siblings`, level by level from
+    /// the leaf up, taking how many belong to each level from the shape of a tree with
+    /// `tree_size` leaves.
+    pub fn new(
+        leaf_idx: usize,
+        tree_size: usize,
+        algorithm: HashAlgorithm,
+        options: TreeOptions,
+        siblings: Vec<Hash>,
+    ) -> Self {
+        let mut siblings = siblings.into_iter();
+        let path = Self::expected_shape(leaf_idx, tree_size, &options)
+            .into_iter()
+            .map(|(position, len)| ProofNode {
+                position,
+                siblings: siblings.by_ref().take(len - 1).collect(),
+            })
+            .collect();
+
+        Self {
+            leaf_idx,
+            tree_size,
+            algorithm,
+            options,
+            path,
+        }
+    }
+
+    /// The position of the node and the number of children of its parent on every level of
+    /// the path of the leaf at `leaf_idx` in a tree of `tree_size` leaves.
+    ///
+    /// `options` must be valid, a branching factor below 2 never reaches the root.
+    pub fn expected_shape(
+        mut leaf_idx: usize,
+        mut tree_size: usize,
+        options: &TreeOptions,
+    ) -> Vec<(usize, usize)> {
+        let mut shape = Vec::new();
+        while tree_size > 1 {
+            let group = options.get_group(leaf_idx, tree_size);
+            if !options.is_promoted(leaf_idx, tree_size) {
+                // a group that isn't full only keeps its size when it isn't padded
+                let len = match options.odd_node_policy {
+                    OddNodePolicy::Promote => group.len(),
+                    _ => options.branching_factor,
+                };
+                shape.push((leaf_idx - group.start, len));
+            }
+            leaf_idx /= options.branching_factor;
+            tree_size = options.get_parent_level_size(tree_size);
+        }
+        shape
+    }
+
+    /// The sibling hashes of the path, without their positions.
+    pub fn sibling_hashes(&self) -> Vec<Hash> {
+        self.path
+            .iter()
+            .flat_map(|node| node.siblings.iter().cloned())
+            .collect()
+    }
+
+    /// Recomputes the root from `leaf_hash` and the sibling path.
+    pub fn compute_root<H: Hasher>(&self, leaf_hash: &Hash, hasher: &H) -> Hash {
+        self.path.iter().fold(leaf_hash.clone(), |hash, node| {
+            let mut children = node.siblings.clone();
+            children.insert(node.position.min(children.len()), hash);
+            hasher.get_children_hash(&children, self.options.hashing_mode)
+        })
+    }
+
+    /// Verifies the proof against a known `root` without needing the tree.
+    ///
+    /// Returns `Ok(())` if the proof was built with the same algorithm as `hasher`, its path
+    /// matches the position of `leaf_idx` in a tree of `tree_size` leaves and hashing
+    /// `leaf_hash` up the path yields `root`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use merkle_tree::mk::compact::CompactMerkleTree;
+    /// use merkle_tree::mk::mk::MerkleTree;
+    /// use merkle_tree::utils::crypto::{Hasher, HashingMode, Sha256Hasher};
+    ///
+    /// let data = vec!["hello", "how", "are", "you"];
+    /// let tree = CompactMerkleTree::create(&data, Sha256Hasher::new()).unwrap();
+    /// let root = tree.root_hash.clone();
+    /// let proof = tree.gen_proof(2).unwrap();
+    ///
+    /// // the verifier side only needs the root, the leaf and the proof
+    /// let hasher = Sha256Hasher::new();
+    /// let leaf_hash = hasher.get_leaf_hash("are", HashingMode::DomainSeparated);
+    /// assert!(proof.verify(&root, &leaf_hash, &hasher).is_ok());
+    /// ```
+    pub fn verify<H: Hasher>(&self, root: &Hash, leaf_hash: &Hash, hasher: &H) -> Result<()> {
+        check_algorithm(hasher, self.algorithm)?;
+        self.check_shape()?;
+        check_hash_lengths(
+            leaf_hash.len(),
+            self.path
+                .iter()
+                .flat_map(|node| &node.siblings)
+                .chain([root]),
+        )?;
+
+        if self.compute_root(leaf_hash, hasher) != *root {
+            return Err(MerkleError::RootMismatch);
+        }
+        Ok(())
+    }
+
+    /// Checks the options and that the path has the positions and the number of siblings of
+    /// the path of `leaf_idx` in a tree of `tree_size` leaves.
+    pub(crate) fn check_shape(&self) -> Result<()> {
+        check_leaf(self.leaf_idx, self.tree_size, &self.options)?;
+        let expected_shape = Self::expected_shape(self.leaf_idx, self.tree_size, &self.options);
+        if expected_shape.len() != self.path.len() {
+            return Err(MerkleError::ProofLengthMismatch {
+                expected: expected_shape.len(),
+                actual: self.path.len(),
+            });
+        }
+        for (step, ((position, len), node)) in expected_shape.iter().zip(&self.path).enumerate() {
+            if *position != node.position {
+                return Err(MerkleError::PositionMismatch { step });
+            }
+            if len - 1 != node.siblings.len() {
+                return Err(MerkleError::ProofLengthMismatch {
+                    expected: len - 1,
+                    actual: node.siblings.len(),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Checks that a tree with `options` can be built and has a leaf at `leaf_idx` when it has
+/// `tree_size` of them.
+pub(crate) fn check_leaf(leaf_idx: usize, tree_size: usize, options: &TreeOptions) -> Result<()> {
+    options.validate()?;
+    if leaf_idx >= tree_size {
+        return Err(MerkleError::IndexOutOfRange {
+            index: leaf_idx,
+            len: tree_size,
+        });
+    }
+    Ok(())
+}
+
+/// Checks that `hashing_mode` hashes children in their order, which proofs relying on the
+/// position of a node need: a sorted pair hashes the same whichever side the node is on.
+pub(crate) fn check_hashing_mode(hashing_mode: HashingMode) -> Result<()> {
+    match hashing_mode {
+        HashingMode::SortedPair => Err(MerkleError::UnsupportedHashingMode { hashing_mode }),
+        HashingMode::Plain | HashingMode::DomainSeparated => Ok(()),
+    }
+}
+
+/// Checks that a proof built with `algorithm` can be verified with `hasher`.
+pub(crate) fn check_algorithm<H: Hasher>(hasher: &H, algorithm: HashAlgorithm) -> Result<()> {
+    if hasher.algorithm() != algorithm {
+        return Err(MerkleError::AlgorithmMismatch {
+            expected: hasher.algorithm(),
+            actual: algorithm,
+        });
+    }
+    Ok(())
+}
+
+/// Checks that every hash in `hashes` is `expected` bytes long.
+pub(crate) fn check_hash_lengths<'a>(
+    expected: usize,
+    hashes: impl IntoIterator<Item = &'a Hash>,
+) -> Result<()> {
+    match hashes.into_iter().find(|hash| hash.len() != expected) {
+        Some(hash) => Err(MerkleError::HashLengthMismatch {
+            expected,
+            actual: hash.len(),
+        }),
+        None => Ok(()),
+    }
+}
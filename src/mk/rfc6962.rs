@@ -0,0 +1,407 @@
+use crate::error::{MerkleError, Result};
+use crate::utils::{crypto::*, num::is_even};
+
+use super::proof::{check_algorithm, check_hash_lengths};
+
+/// The largest power of two smaller than `size`, which must be at least 2.
+fn get_split(size: usize) -> usize {
+    1 << (usize::BITS - 1 - (size - 1).leading_zeros())
+}
+
+/// The number of hashes in the audit path of the leaf at `leaf_idx` in a tree of `size`.
+fn get_audit_path_len(leaf_idx: usize, size: usize) -> usize {
+    if size <= 1 {
+        return 0;
+    }
+    let split = get_split(size);
+    match leaf_idx < split {
+        true => 1 + get_audit_path_len(leaf_idx, split),
+        false => 1 + get_audit_path_len(leaf_idx - split, size - split),
+    }
+}
+
+/// The number of hashes in `SUBPROOF(old_size, D[new_size], is_complete)`.
+fn get_consistency_path_len(old_size: usize, new_size: usize, is_complete: bool) -> usize {
+    if old_size == new_size {
+        return usize::from(!is_complete);
+    }
+    let split = get_split(new_size);
+    match old_size <= split {
+        true => 1 + get_consistency_path_len(old_size, split, is_complete),
+        false => 1 + get_consistency_path_len(old_size - split, new_size - split, false),
+    }
+}
+
+/// Right-shifts `a` and `b` until the least significant bit of `a` is set or it is zero.
+fn shift_while_even(a: &mut usize, b: &mut usize) {
+    while *a & 1 == 0 && *a != 0 {
+        *a >>= 1;
+        *b >>= 1;
+    }
+}
+
+/// Right-shifts `a` and `b` while the least significant bit of `a` is set.
+fn shift_while_odd(a: &mut usize, b: &mut usize) {
+    while *a & 1 == 1 {
+        *a >>= 1;
+        *b >>= 1;
+    }
+}
+
+/// An append-only tree exactly as specified by RFC 6962 (and RFC 9162) for Certificate
+/// Transparency logs.
+///
+/// Leaves are hashed as `H(0x00 || data)` and nodes as `H(0x01 || left || right)`, and a tree
+/// of `n` leaves is split at the largest power of two smaller than `n`. Audit paths and
+/// consistency proofs are the lists of hashes of the RFC, so they interoperate with real logs.
+#[derive(Clone)]
+pub struct Rfc6962MerkleTree<H: Hasher> {
+    pub hasher: H,
+    /// The roots of every complete subtree by height, `levels[0]` being the leaves.
+    levels: Vec<Vec<Hash>>,
+}
+
+impl<H: Hasher> Rfc6962MerkleTree<H> {
+    pub fn new(hasher: H) -> Self {
+        Self {
+            hasher,
+            levels: vec![Vec::new()],
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.levels[0].len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.levels[0].is_empty()
+    }
+
+    /// Hashes `data` the way the RFC hashes leaves.
+    pub fn get_leaf_hash<T: HashableData>(&self, data: T) -> Hash {
+        self.hasher
+            .get_leaf_hash(data, HashingMode::DomainSeparated)
+    }
+
+    pub fn get_leaf(&self, idx: usize) -> Option<&Hash> {
+        self.levels[0].get(idx)
+    }
+
+    /// Appends a leaf and returns its index.
+    pub fn append<T: HashableData>(&mut self, data: T) -> usize {
+        let leaf = self.get_leaf_hash(data);
+        self.levels[0].push(leaf);
+
+        // every subtree the new leaf completes is kept
+        let mut height = 0;
+        while is_even(self.levels[height].len()) {
+            let nodes = &self.levels[height];
+            let parent = self.get_node_hash(&nodes[nodes.len() - 2], &nodes[nodes.len() - 1]);
+            height += 1;
+            if self.levels.len() == height {
+                self.levels.push(Vec::new());
+            }
+            self.levels[height].push(parent);
+        }
+
+        self.len() - 1
+    }
+
+    /// The current root, the hash of an empty string if nothing has been appended yet.
+    pub fn root_hash(&self) -> Hash {
+        self.get_root_at(self.len()).unwrap()
+    }
+
+    /// The root the tree had with `size` leaves.
+    pub fn get_root_at(&self, size: usize) -> Result<Hash> {
+        self.check_size(size)?;
+        match size {
+            0 => Ok(self.hasher.get_hash_from_data([])),
+            size => Ok(self.get_subtree_root(0, size)),
+        }
+    }
+
+    /// Generates the audit path of the leaf at `leaf_idx` against the current root.
+    pub fn gen_audit_path(&self, leaf_idx: usize) -> Result<AuditPath> {
+        self.gen_audit_path_at(leaf_idx, self.len())
+    }
+
+    /// Generates the audit path of the leaf at `leaf_idx` against the root the tree had with
+    /// `size` leaves, `PATH(leaf_idx, D[size])` in the RFC.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use merkle_tree::mk::rfc6962::Rfc6962MerkleTree;
+    /// use merkle_tree::utils::crypto::Sha256Hasher;
+    ///
+    /// let mut log = Rfc6962MerkleTree::new(Sha256Hasher::new());
+    /// for cert in ["first", "second", "third"] {
+    ///     log.append(cert);
+    /// }
+    /// let path = log.gen_audit_path(1).unwrap();
+    /// let leaf_hash = log.get_leaf_hash("second");
+    /// assert!(path.verify(&log.root_hash(), &leaf_hash, &log.hasher).is_ok());
+    /// ```
+    pub fn gen_audit_path_at(&self, leaf_idx: usize, size: usize) -> Result<AuditPath> {
+        self.check_size(size)?;
+        if leaf_idx >= size {
+            return Err(MerkleError::IndexOutOfRange {
+                index: leaf_idx,
+                len: size,
+            });
+        }
+
+        let mut path = Vec::new();
+        self.push_audit_path(leaf_idx, 0, size, &mut path);
+
+        Ok(AuditPath {
+            leaf_idx,
+            tree_size: size,
+            algorithm: self.hasher.algorithm(),
+            path,
+        })
+    }
+
+    /// Generates the proof that the tree with `old_size` leaves is a prefix of the one with
+    /// `new_size`, `PROOF(old_size, D[new_size])` in the RFC.
+    pub fn gen_consistency_path(
+        &self,
+        old_size: usize,
+        new_size: usize,
+    ) -> Result<ConsistencyPath> {
+        if old_size == 0 || old_size > new_size || new_size > self.len() {
+            return Err(MerkleError::InvalidSizes {
+                old_size,
+                new_size,
+                len: self.len(),
+            });
+        }
+
+        let mut path = Vec::new();
+        if old_size < new_size {
+            self.push_consistency_path(old_size, 0, new_size, true, &mut path);
+        }
+
+        Ok(ConsistencyPath {
+            old_size,
+            new_size,
+            algorithm: self.hasher.algorithm(),
+            path,
+        })
+    }
+
+    fn get_node_hash(&self, left: &Hash, right: &Hash) -> Hash {
+        self.hasher
+            .get_node_hash(left, right, HashingMode::DomainSeparated)
+    }
+
+    /// `MTH(D[start:end])`, read from the complete subtrees it is made of.
+    fn get_subtree_root(&self, start: usize, end: usize) -> Hash {
+        let size = end - start;
+        if size.is_power_of_two() && start.is_multiple_of(size) {
+            let height = size.trailing_zeros() as usize;
+            return self.levels[height][start >> height].clone();
+        }
+        let split = start + get_split(size);
+        self.get_node_hash(
+            &self.get_subtree_root(start, split),
+            &self.get_subtree_root(split, end),
+        )
+    }
+
+    /// Pushes `PATH(leaf_idx, D[start:end])`, from the leaf up.
+    fn push_audit_path(&self, leaf_idx: usize, start: usize, end: usize, path: &mut Vec<Hash>) {
+        if end - start <= 1 {
+            return;
+        }
+        let split = start + get_split(end - start);
+        if leaf_idx < split {
+            self.push_audit_path(leaf_idx, start, split, path);
+            path.push(self.get_subtree_root(split, end));
+        } else {
+            self.push_audit_path(leaf_idx, split, end, path);
+            path.push(self.get_subtree_root(start, split));
+        }
+    }
+
+    /// Pushes `SUBPROOF(old_size, D[start:end], is_complete)`, from the leaves up.
+    fn push_consistency_path(
+        &self,
+        old_size: usize,
+        start: usize,
+        end: usize,
+        is_complete: bool,
+        path: &mut Vec<Hash>,
+    ) {
+        if start + old_size == end {
+            // the verifier already knows the old root if it is this whole subtree
+            if !is_complete {
+                path.push(self.get_subtree_root(start, end));
+            }
+            return;
+        }
+        let split = get_split(end - start);
+        if old_size <= split {
+            self.push_consistency_path(old_size, start, start + split, is_complete, path);
+            path.push(self.get_subtree_root(start + split, end));
+        } else {
+            self.push_consistency_path(old_size - split, start + split, end, false, path);
+            path.push(self.get_subtree_root(start, start + split));
+        }
+    }
+
+    fn check_size(&self, size: usize) -> Result<()> {
+        if size > self.len() {
+            return Err(MerkleError::IndexOutOfRange {
+                index: size,
+                len: self.len(),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Proves that a leaf is in an RFC 6962 tree of `tree_size` leaves.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditPath {
+    pub leaf_idx: usize,
+    pub tree_size: usize,
+    pub algorithm: HashAlgorithm,
+    /// The hashes of the RFC, from the leaf up.
+    pub path: Vec<Hash>,
+}
+
+impl AuditPath {
+    /// Recomputes the root from `leaf_hash` as RFC 9162 (section 2.1.3.2) does.
+    pub fn compute_root<H: Hasher>(&self, leaf_hash: &Hash, hasher: &H) -> Result<Hash> {
+        check_algorithm(hasher, self.algorithm)?;
+        if self.leaf_idx >= self.tree_size {
+            return Err(MerkleError::IndexOutOfRange {
+                index: self.leaf_idx,
+                len: self.tree_size,
+            });
+        }
+        let expected = get_audit_path_len(self.leaf_idx, self.tree_size);
+        if self.path.len() != expected {
+            return Err(MerkleError::ProofLengthMismatch {
+                expected,
+                actual: self.path.len(),
+            });
+        }
+        check_hash_lengths(leaf_hash.len(), &self.path)?;
+
+        let mode = HashingMode::DomainSeparated;
+        let (mut node_idx, mut last_idx) = (self.leaf_idx, self.tree_size - 1);
+        let mut hash = leaf_hash.clone();
+        for sibling in &self.path {
+            if node_idx & 1 == 1 || node_idx == last_idx {
+                hash = hasher.get_node_hash(sibling, &hash, mode);
+                shift_while_even(&mut node_idx, &mut last_idx);
+            } else {
+                hash = hasher.get_node_hash(&hash, sibling, mode);
+            }
+            node_idx >>= 1;
+            last_idx >>= 1;
+        }
+
+        Ok(hash)
+    }
+
+    /// Verifies the audit path against the `root` of the tree with `tree_size` leaves.
+    pub fn verify<H: Hasher>(&self, root: &Hash, leaf_hash: &Hash, hasher: &H) -> Result<()> {
+        if self.compute_root(leaf_hash, hasher)? != *root {
+            return Err(MerkleError::RootMismatch);
+        }
+        Ok(())
+    }
+}
+
+/// Proves that an RFC 6962 tree of `old_size` leaves is a prefix of the one of `new_size`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConsistencyPath {
+    pub old_size: usize,
+    pub new_size: usize,
+    pub algorithm: HashAlgorithm,
+    /// The hashes of the RFC, from the leaves up.
+    pub path: Vec<Hash>,
+}
+
+impl ConsistencyPath {
+    /// Recomputes the old and new roots as RFC 9162 (section 2.1.4.2) does. The path doesn't
+    /// hold the old root when it is a complete subtree, so it is taken from `old_root`.
+    pub fn compute_roots<H: Hasher>(&self, old_root: &Hash, hasher: &H) -> Result<(Hash, Hash)> {
+        check_algorithm(hasher, self.algorithm)?;
+        if self.old_size == 0 || self.old_size > self.new_size {
+            return Err(MerkleError::InvalidSizes {
+                old_size: self.old_size,
+                new_size: self.new_size,
+                len: self.new_size,
+            });
+        }
+        let expected = match self.old_size == self.new_size {
+            true => 0,
+            false => get_consistency_path_len(self.old_size, self.new_size, true),
+        };
+        if self.path.len() != expected {
+            return Err(MerkleError::ProofLengthMismatch {
+                expected,
+                actual: self.path.len(),
+            });
+        }
+        check_hash_lengths(old_root.len(), &self.path)?;
+        if self.old_size == self.new_size {
+            return Ok((old_root.clone(), old_root.clone()));
+        }
+
+        let mut path = self.path.iter();
+        let first = match self.old_size.is_power_of_two() {
+            true => old_root,
+            false => path.next().unwrap(),
+        };
+
+        let mode = HashingMode::DomainSeparated;
+        let (mut old_idx, mut new_idx) = (self.old_size - 1, self.new_size - 1);
+        let (mut old_hash, mut new_hash) = (first.clone(), first.clone());
+        shift_while_odd(&mut old_idx, &mut new_idx);
+        for sibling in path {
+            if old_idx & 1 == 1 || old_idx == new_idx {
+                old_hash = hasher.get_node_hash(sibling, &old_hash, mode);
+                new_hash = hasher.get_node_hash(sibling, &new_hash, mode);
+                shift_while_even(&mut old_idx, &mut new_idx);
+            } else {
+                new_hash = hasher.get_node_hash(&new_hash, sibling, mode);
+            }
+            old_idx >>= 1;
+            new_idx >>= 1;
+        }
+
+        Ok((old_hash, new_hash))
+    }
+
+    /// Verifies that the tree with `old_root` is a prefix of the one with `new_root`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use merkle_tree::mk::rfc6962::Rfc6962MerkleTree;
+    /// use merkle_tree::utils::crypto::Sha256Hasher;
+    ///
+    /// let mut log = Rfc6962MerkleTree::new(Sha256Hasher::new());
+    /// for cert in ["first", "second", "third"] {
+    ///     log.append(cert);
+    /// }
+    /// let old_root = log.root_hash();
+    /// log.append("fourth");
+    ///
+    /// let proof = log.gen_consistency_path(3, 4).unwrap();
+    /// assert!(proof.verify(&old_root, &log.root_hash(), &log.hasher).is_ok());
+    /// ```
+    pub fn verify<H: Hasher>(&self, old_root: &Hash, new_root: &Hash, hasher: &H) -> Result<()> {
+        let (old_hash, new_hash) = self.compute_roots(old_root, hasher)?;
+        if old_hash != *old_root || new_hash != *new_root {
+            return Err(MerkleError::RootMismatch);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,81 @@
+use std::sync::{Arc, Mutex, PoisonError, RwLock};
+
+/// A tree that can be cloned into several threads or async tasks, readers generating proofs
+/// while a writer builds the next version of the tree.
+///
+/// Every tree of this crate is `Send + Sync` as long as its hasher is, which the provided
+/// hashers are. This handle publishes the tree as an `Arc<T>`: a reader only locks long
+/// enough to clone the `Arc` of the last version, and keeps working on it however long a
+/// write takes. A writer updates a copy of that version and swaps it in once done, so readers
+/// see the tree either before or after a write, never in between. Writes copy the tree, and
+/// run one at a time so that none of them loses the changes of another.
+///
+/// # Examples
+///
+/// ```
+/// use std::thread;
+///
+/// use merkle_tree::mk::compact::CompactMerkleTree;
+/// use merkle_tree::mk::mk::MerkleTree;
+/// use merkle_tree::mk::shared::SharedMerkleTree;
+/// use merkle_tree::utils::crypto::Sha256Hasher;
+///
+/// let tree = CompactMerkleTree::create(&["hello"], Sha256Hasher::new()).unwrap();
+/// let shared = SharedMerkleTree::new(tree);
+///
+/// let writer = shared.clone();
+/// thread::spawn(move || writer.write(|tree| tree.add_leaf("world")))
+///     .join()
+///     .unwrap();
+///
+/// // the root and the proof come from the same version of the tree
+/// let tree = shared.read();
+/// let proof = tree.gen_proof(1).unwrap();
+/// assert!(tree.verify_proof(&tree.get_leaf_hash("world"), &proof).is_ok());
+/// ```
+pub struct SharedMerkleTree<T> {
+    /// The last version published by a writer.
+    tree: Arc<RwLock<Arc<T>>>,
+    /// Held for the whole of a write, so writers build on each other's versions.
+    writer: Arc<Mutex<()>>,
+}
+
+impl<T> SharedMerkleTree<T> {
+    pub fn new(tree: T) -> Self {
+        Self {
+            tree: Arc::new(RwLock::new(Arc::new(tree))),
+            writer: Arc::new(Mutex::new(())),
+        }
+    }
+
+    /// The last version of the tree, which stays the same however the tree is written after.
+    pub fn read(&self) -> Arc<T> {
+        // the lock is only held to clone or swap the `Arc`, which can't leave it half updated
+        let tree = self.tree.read().unwrap_or_else(PoisonError::into_inner);
+        Arc::clone(&tree)
+    }
+
+    /// Applies `update` to a copy of the last version of the tree and publishes it, waiting
+    /// for the writer before if there is one. Readers aren't blocked meanwhile.
+    ///
+    /// If `update` panics, nothing is published and the tree stays as it was.
+    pub fn write<R>(&self, update: impl FnOnce(&mut T) -> R) -> R
+    where
+        T: Clone,
+    {
+        let _writer = self.writer.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut next = T::clone(&self.read());
+        let result = update(&mut next);
+        *self.tree.write().unwrap_or_else(PoisonError::into_inner) = Arc::new(next);
+        result
+    }
+}
+
+impl<T> Clone for SharedMerkleTree<T> {
+    fn clone(&self) -> Self {
+        Self {
+            tree: Arc::clone(&self.tree),
+            writer: Arc::clone(&self.writer),
+        }
+    }
+}
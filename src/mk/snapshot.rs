@@ -0,0 +1,39 @@
+use crate::error::{MerkleError, Result};
+use crate::utils::crypto::{Hash, HashAlgorithm, Hasher};
+
+use super::options::TreeOptions;
+
+/// Everything needed to load a `CompactMerkleTree` or a `FullMerkleTree` back without the data
+/// it was built from: the leaf hashes, the levels above them and the root.
+///
+/// With the `serde` feature both trees serialize to a snapshot and deserialize from one, as
+/// long as their hasher implements `Default`. Loading rehashes the levels from the leaves and
+/// rejects the snapshot if any cached hash differs, so a tampered snapshot can't pass for the
+/// tree it claims to be. The leaf index isn't saved, enable it again after loading.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TreeSnapshot {
+    /// The hash function of the tree, loading it with another one fails.
+    pub algorithm: HashAlgorithm,
+    pub options: TreeOptions,
+    pub leaves: Vec<Hash>,
+    /// Levels above the leaves, `levels[0]` being their parents and the last one the root.
+    pub levels: Vec<Vec<Hash>>,
+    pub root_hash: Hash,
+}
+
+impl TreeSnapshot {
+    /// Checks the snapshot can be loaded into a tree with `hasher`, before rebuilding it.
+    pub(crate) fn check<H: Hasher>(&self, hasher: &H) -> Result<()> {
+        if self.algorithm != hasher.algorithm() {
+            return Err(MerkleError::AlgorithmMismatch {
+                expected: hasher.algorithm(),
+                actual: self.algorithm,
+            });
+        }
+        if self.leaves.is_empty() {
+            return Err(MerkleError::EmptyInput);
+        }
+        self.options.validate()
+    }
+}
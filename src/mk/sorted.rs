@@ -0,0 +1,211 @@
+use crate::error::{MerkleError, Result};
+use crate::utils::crypto::*;
+
+use super::{
+    compact::CompactMerkleTree,
+    mk::MerkleTree,
+    options::TreeOptions,
+    proof::{check_hashing_mode, MerkleProof},
+};
+
+/// A Merkle tree whose leaf hashes are sorted and unique, so the absence of a value can be
+/// proven by the two adjacent leaves around where its hash would be.
+///
+/// Leaves can't be written once the tree is built, as that would break their order, but the
+/// underlying tree is available through `tree` for everything else. Non-membership proofs
+/// are only sound if the root comes from a sorted tree, so check where it comes from.
+///
+/// A tree hashed with `HashingMode::SortedPair` only gives inclusion proofs: sorted pairs
+/// don't commit to the position of a leaf, which non-membership proofs rely on.
+#[derive(Clone)]
+pub struct SortedMerkleTree<H: Hasher> {
+    tree: CompactMerkleTree<H>,
+    /// The data of every leaf, in the order of their hashes, which non-membership proofs carry.
+    data: Vec<Vec<u8>>,
+}
+
+impl<H: Hasher> SortedMerkleTree<H> {
+    pub fn create<T: HashableData>(data: &[T], hasher: H) -> Result<Self> {
+        SortedMerkleTree::create_with_options(data, hasher, TreeOptions::default())
+    }
+
+    pub fn create_with_options<T: HashableData>(
+        data: &[T],
+        hasher: H,
+        options: TreeOptions,
+    ) -> Result<Self> {
+        let mut leaves: Vec<(Hash, Vec<u8>)> = data
+            .iter()
+            .map(|el| {
+                (
+                    hasher.get_leaf_hash(el, options.hashing_mode),
+                    el.as_ref().to_vec(),
+                )
+            })
+            .collect();
+        leaves.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+        leaves.dedup_by(|(a, _), (b, _)| a == b);
+        let (leaf_hashes, data) = leaves.into_iter().unzip();
+
+        Ok(Self {
+            tree: CompactMerkleTree::create_from_leaf_hashes(leaf_hashes, hasher, options)?,
+            data,
+        })
+    }
+
+    pub fn tree(&self) -> &CompactMerkleTree<H> {
+        &self.tree
+    }
+
+    pub fn root_hash(&self) -> &Hash {
+        &self.tree.root_hash
+    }
+
+    /// The number of unique leaves, which non-membership proofs are verified against.
+    pub fn len(&self) -> usize {
+        self.tree.leaves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tree.leaves.is_empty()
+    }
+
+    /// Hashes `data` the way this tree hashes its leaves.
+    pub fn get_leaf_hash<T: HashableData>(&self, data: T) -> Hash {
+        self.tree.get_leaf_hash(data)
+    }
+
+    pub fn contains<T: HashableData>(&self, data: T) -> bool {
+        self.search(&self.get_leaf_hash(data)).is_ok()
+    }
+
+    /// Generates an inclusion proof for `data`, or `None` if it isn't in the tree.
+    pub fn gen_proof<T: HashableData>(&self, data: T) -> Option<MerkleProof> {
+        let idx = self.search(&self.get_leaf_hash(data)).ok()?;
+        self.tree.gen_proof(idx).ok()
+    }
+
+    /// Generates a proof that `data` isn't in the tree, or `None` if it is. Fails if the tree
+    /// is hashed with `SortedPair`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use merkle_tree::mk::sorted::SortedMerkleTree;
+    /// use merkle_tree::utils::crypto::Sha256Hasher;
+    ///
+    /// let allowlist = ["alice", "bob", "carol"];
+    /// let tree = SortedMerkleTree::create(&allowlist, Sha256Hasher::new()).unwrap();
+    /// assert!(tree.gen_non_membership_proof("bob").unwrap().is_none());
+    ///
+    /// let proof = tree.gen_non_membership_proof("mallory").unwrap().unwrap();
+    /// let leaf_hash = tree.get_leaf_hash("mallory");
+    /// let hasher = Sha256Hasher::new();
+    /// assert!(proof.verify(tree.root_hash(), tree.len(), &leaf_hash, &hasher).is_ok());
+    /// ```
+    pub fn gen_non_membership_proof<T: HashableData>(
+        &self,
+        data: T,
+    ) -> Result<Option<NonMembershipProof>> {
+        check_hashing_mode(self.tree.options.hashing_mode)?;
+        // the value would be right before the leaf at `idx`
+        let Err(idx) = self.search(&self.get_leaf_hash(data)) else {
+            return Ok(None);
+        };
+        let get_neighbor = |idx: usize| Neighbor {
+            data: self.data[idx].clone(),
+            proof: self.tree.gen_proof(idx).unwrap(),
+        };
+
+        Ok(Some(NonMembershipProof {
+            left: idx.checked_sub(1).map(get_neighbor),
+            right: (idx < self.tree.leaves.len()).then(|| get_neighbor(idx)),
+        }))
+    }
+
+    fn search(&self, hash: &Hash) -> std::result::Result<usize, usize> {
+        self.tree
+            .leaves
+            .binary_search_by(|leaf| leaf.value.cmp(hash))
+    }
+}
+
+/// A leaf of a sorted tree next to an absent value, with the proof of its position.
+///
+/// It carries the data of the leaf rather than its hash, so that only a leaf can be a
+/// neighbor: an interior node or the root isn't the leaf hash of any data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Neighbor {
+    pub data: Vec<u8>,
+    pub proof: MerkleProof,
+}
+
+impl Neighbor {
+    /// The hash of the leaf in a tree with the options of the proof.
+    pub fn leaf_hash<H: Hasher>(&self, hasher: &H) -> Hash {
+        hasher.get_leaf_hash(&self.data, self.proof.options.hashing_mode)
+    }
+}
+
+/// Proves that a leaf hash isn't in a `SortedMerkleTree` with the two adjacent leaves around
+/// it. The first and last leaves only have a neighbor on one side.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NonMembershipProof {
+    /// The greatest leaf lower than the absent one, if any.
+    pub left: Option<Neighbor>,
+    /// The lowest leaf greater than the absent one, if any.
+    pub right: Option<Neighbor>,
+}
+
+impl NonMembershipProof {
+    /// Verifies that `leaf_hash` isn't in the sorted tree with `root` and `tree_size` leaves:
+    /// both neighbors must be leaves of it, next to each other, and the hash must fall
+    /// strictly between theirs.
+    ///
+    /// `tree_size` must come from the same place as `root`, never from the proof: at a smaller
+    /// size, two adjacent interior nodes would pass for leaves.
+    pub fn verify<H: Hasher>(
+        &self,
+        root: &Hash,
+        tree_size: usize,
+        leaf_hash: &Hash,
+        hasher: &H,
+    ) -> Result<()> {
+        let options = match (&self.left, &self.right) {
+            (Some(left), _) => left.proof.options,
+            (None, Some(right)) => right.proof.options,
+            (None, None) => return Err(MerkleError::NotBracketed),
+        };
+        check_hashing_mode(options.hashing_mode)?;
+
+        let mut neighbor_hashes = Vec::with_capacity(2);
+        for neighbor in self.left.iter().chain(&self.right) {
+            if neighbor.proof.options != options {
+                return Err(MerkleError::OptionsMismatch);
+            }
+            if neighbor.proof.tree_size != tree_size {
+                return Err(MerkleError::NotBracketed);
+            }
+            let neighbor_hash = neighbor.leaf_hash(hasher);
+            neighbor.proof.verify(root, &neighbor_hash, hasher)?;
+            neighbor_hashes.push(neighbor_hash);
+        }
+
+        let is_bracketed = match (&self.left, &self.right) {
+            (Some(left), Some(right)) => {
+                left.proof.leaf_idx + 1 == right.proof.leaf_idx
+                    && neighbor_hashes[0] < *leaf_hash
+                    && *leaf_hash < neighbor_hashes[1]
+            }
+            (Some(left), None) => {
+                left.proof.leaf_idx + 1 == tree_size && neighbor_hashes[0] < *leaf_hash
+            }
+            (None, Some(right)) => right.proof.leaf_idx == 0 && *leaf_hash < neighbor_hashes[0],
+            (None, None) => unreachable!(),
+        };
+        if !is_bracketed {
+            return Err(MerkleError::NotBracketed);
+        }
+        Ok(())
+    }
+}
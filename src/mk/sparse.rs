@@ -0,0 +1,264 @@
+use std::collections::HashMap;
+
+use crate::error::{MerkleError, Result};
+use crate::utils::crypto::*;
+
+use super::proof::{check_algorithm, check_hash_lengths, check_hashing_mode};
+
+/// A key of the map, which is also the path from the root to its leaf: the most significant
+/// bit picks the child of the root and the least significant one the leaf.
+pub type Key = [u8; 32];
+
+/// Number of levels between the leaves and the root.
+pub const DEPTH: usize = 256;
+
+/// Whether the bit at `height` is set, height 0 being the least significant bit.
+fn get_bit(bytes: &Key, height: usize) -> bool {
+    bytes[31 - height / 8] & (1 << (height % 8)) != 0
+}
+
+fn set_bit(bytes: &mut Key, height: usize) {
+    bytes[31 - height / 8] |= 1 << (height % 8);
+}
+
+/// The prefix of the sibling of the node at `height` with `prefix`.
+fn get_sibling_prefix(prefix: &Key, height: usize) -> Key {
+    let mut sibling = *prefix;
+    sibling[31 - height / 8] ^= 1 << (height % 8);
+    sibling
+}
+
+/// The first node of the subtree at `height` that holds `key`, with the bits below it cleared.
+fn get_prefix(key: &Key, height: usize) -> Key {
+    let mut prefix = *key;
+    for bit in 0..height {
+        prefix[31 - bit / 8] &= !(1 << (bit % 8));
+    }
+    prefix
+}
+
+/// The hashes of empty subtrees for every height, the empty leaf being all zeros.
+pub fn get_default_hashes<H: Hasher>(hasher: &H, hashing_mode: HashingMode) -> Vec<Hash> {
+    let len = hasher.get_leaf_hash([], hashing_mode).len();
+    let mut hashes = vec![vec![0; len]];
+    for height in 0..DEPTH {
+        let empty = &hashes[height];
+        hashes.push(hasher.get_node_hash(empty, empty, hashing_mode));
+    }
+    hashes
+}
+
+/// A Merkle tree committing to a map of 256-bit keys, where every possible key has a leaf.
+///
+/// Almost every subtree is empty, so their hashes are computed once and only the nodes
+/// above a value are stored. This allows proving that a key is not in the map as well.
+#[derive(Clone)]
+pub struct SparseMerkleTree<H: Hasher> {
+    pub hasher: H,
+    pub hashing_mode: HashingMode,
+    pub root_hash: Hash,
+    values: HashMap<Key, Vec<u8>>,
+    /// Non empty nodes by height and prefix of their keys.
+    nodes: HashMap<(usize, Key), Hash>,
+    default_hashes: Vec<Hash>,
+}
+
+impl<H: Hasher> SparseMerkleTree<H> {
+    pub fn new(hasher: H) -> Self {
+        SparseMerkleTree::new_empty(hasher, HashingMode::default())
+    }
+
+    /// Creates an empty map hashed with `Plain` or `DomainSeparated`; `SortedPair` is
+    /// rejected, as it would let the proof of a key pass for the proof of its neighbor.
+    pub fn new_with_hashing_mode(hasher: H, hashing_mode: HashingMode) -> Result<Self> {
+        check_hashing_mode(hashing_mode)?;
+        Ok(SparseMerkleTree::new_empty(hasher, hashing_mode))
+    }
+
+    fn new_empty(hasher: H, hashing_mode: HashingMode) -> Self {
+        let default_hashes = get_default_hashes(&hasher, hashing_mode);
+
+        Self {
+            root_hash: default_hashes[DEPTH].clone(),
+            hasher,
+            hashing_mode,
+            values: HashMap::new(),
+            nodes: HashMap::new(),
+            default_hashes,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn get(&self, key: &Key) -> Option<&[u8]> {
+        self.values.get(key).map(|value| value.as_slice())
+    }
+
+    /// Sets the value of `key`, returning the previous one if there was any.
+    pub fn insert<T: HashableData>(&mut self, key: Key, value: T) -> Option<Vec<u8>> {
+        let leaf = self.hasher.get_leaf_hash(&value, self.hashing_mode);
+        self.update_path(&key, leaf);
+        self.values.insert(key, value.as_ref().to_vec())
+    }
+
+    /// Removes `key` from the map, returning its value if there was any.
+    pub fn remove(&mut self, key: &Key) -> Option<Vec<u8>> {
+        let value = self.values.remove(key)?;
+        self.update_path(key, self.default_hashes[0].clone());
+        Some(value)
+    }
+
+    /// Proves the value of `key`, or that there is none.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use merkle_tree::mk::sparse::SparseMerkleTree;
+    /// use merkle_tree::utils::crypto::Sha256Hasher;
+    ///
+    /// let mut tree = SparseMerkleTree::new(Sha256Hasher::new());
+    /// tree.insert([1; 32], "hello");
+    ///
+    /// let proof = tree.gen_proof(&[1; 32]);
+    /// assert!(proof.verify_inclusion(&tree.root_hash, "hello", &tree.hasher).is_ok());
+    ///
+    /// let proof = tree.gen_proof(&[2; 32]);
+    /// assert!(proof.verify_exclusion(&tree.root_hash, &tree.hasher).is_ok());
+    /// ```
+    pub fn gen_proof(&self, key: &Key) -> SparseMerkleProof {
+        let mut non_empty = [0; 32];
+        let mut siblings = Vec::new();
+
+        for height in 0..DEPTH {
+            let sibling = get_sibling_prefix(&get_prefix(key, height), height);
+            if let Some(hash) = self.nodes.get(&(height, sibling)) {
+                set_bit(&mut non_empty, height);
+                siblings.push(hash.clone());
+            }
+        }
+
+        SparseMerkleProof {
+            key: *key,
+            algorithm: self.hasher.algorithm(),
+            hashing_mode: self.hashing_mode,
+            non_empty,
+            siblings,
+        }
+    }
+
+    fn get_node(&self, height: usize, prefix: &Key) -> &Hash {
+        self.nodes
+            .get(&(height, *prefix))
+            .unwrap_or(&self.default_hashes[height])
+    }
+
+    fn set_node(&mut self, height: usize, prefix: Key, hash: Hash) {
+        if hash == self.default_hashes[height] {
+            self.nodes.remove(&(height, prefix));
+        } else {
+            self.nodes.insert((height, prefix), hash);
+        }
+    }
+
+    /// Sets the leaf of `key` and rehashes its ancestors.
+    fn update_path(&mut self, key: &Key, leaf: Hash) {
+        let mut hash = leaf;
+
+        for height in 0..DEPTH {
+            let prefix = get_prefix(key, height);
+            let sibling_hash = self.get_node(height, &get_sibling_prefix(&prefix, height));
+
+            let parent = if get_bit(key, height) {
+                self.hasher
+                    .get_node_hash(sibling_hash, &hash, self.hashing_mode)
+            } else {
+                self.hasher
+                    .get_node_hash(&hash, sibling_hash, self.hashing_mode)
+            };
+            self.set_node(height, prefix, hash);
+            hash = parent;
+        }
+
+        self.root_hash = hash;
+    }
+}
+
+/// Proves the value of a key in a `SparseMerkleTree`, or that it has none.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SparseMerkleProof {
+    pub key: Key,
+    pub algorithm: HashAlgorithm,
+    pub hashing_mode: HashingMode,
+    /// The bit of every height whose sibling isn't an empty subtree, with the same layout as
+    /// the key.
+    pub non_empty: Key,
+    /// The siblings that aren't empty subtrees, from the leaf level up.
+    pub siblings: Vec<Hash>,
+}
+
+impl SparseMerkleProof {
+    /// Recomputes the root from the hash of the leaf of `key`.
+    pub fn compute_root<H: Hasher>(&self, leaf_hash: &Hash, hasher: &H) -> Result<Hash> {
+        check_algorithm(hasher, self.algorithm)?;
+        check_hashing_mode(self.hashing_mode)?;
+        let expected = self
+            .non_empty
+            .iter()
+            .map(|byte| byte.count_ones())
+            .sum::<u32>() as usize;
+        if expected != self.siblings.len() {
+            return Err(MerkleError::ProofLengthMismatch {
+                expected,
+                actual: self.siblings.len(),
+            });
+        }
+        check_hash_lengths(leaf_hash.len(), &self.siblings)?;
+
+        let default_hashes = get_default_hashes(hasher, self.hashing_mode);
+        let mut siblings = self.siblings.iter();
+        let mut hash = leaf_hash.clone();
+
+        for (height, default_hash) in default_hashes.iter().enumerate().take(DEPTH) {
+            let sibling = match get_bit(&self.non_empty, height) {
+                true => siblings.next().unwrap(),
+                false => default_hash,
+            };
+            hash = match get_bit(&self.key, height) {
+                true => hasher.get_node_hash(sibling, &hash, self.hashing_mode),
+                false => hasher.get_node_hash(&hash, sibling, self.hashing_mode),
+            };
+        }
+
+        Ok(hash)
+    }
+
+    /// Verifies that `key` holds `value` in the tree with `root`.
+    pub fn verify_inclusion<H: Hasher, T: HashableData>(
+        &self,
+        root: &Hash,
+        value: T,
+        hasher: &H,
+    ) -> Result<()> {
+        let leaf_hash = hasher.get_leaf_hash(value, self.hashing_mode);
+        self.verify(root, &leaf_hash, hasher)
+    }
+
+    /// Verifies that `key` holds no value in the tree with `root`.
+    pub fn verify_exclusion<H: Hasher>(&self, root: &Hash, hasher: &H) -> Result<()> {
+        let len = hasher.get_leaf_hash([], self.hashing_mode).len();
+        self.verify(root, &vec![0; len], hasher)
+    }
+
+    fn verify<H: Hasher>(&self, root: &Hash, leaf_hash: &Hash, hasher: &H) -> Result<()> {
+        if self.compute_root(leaf_hash, hasher)? != *root {
+            return Err(MerkleError::RootMismatch);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,136 @@
+use crate::error::{MerkleError, Result};
+use crate::utils::{crypto::*, num::is_even};
+
+/// The tree of OpenZeppelin's `StandardMerkleTree`, giving the same root and proofs as its
+/// JavaScript library for any number of leaves. Use it with a `Keccak256Hasher` and the
+/// ABI-encoded values as data.
+///
+/// Leaves are hashed as `H(H(data))`, sorted, and stored with their parents in a heap: the
+/// leaves at the end in reverse order and the children of the node at `i` at `2i + 1` and
+/// `2i + 2`. This pairs nodes differently than promoting the lone node of every level does,
+/// so `TreeOptions::openzeppelin()` only gives the same root for a power of two leaves or three
+/// times one.
+///
+/// # Examples
+///
+/// ```
+/// use merkle_tree::mk::standard::{process_proof, StandardMerkleTree};
+/// use merkle_tree::utils::crypto::Keccak256Hasher;
+///
+/// let values = ["a", "b", "c", "d", "e"];
+/// let tree = StandardMerkleTree::create(&values, Keccak256Hasher::new()).unwrap();
+///
+/// // the `bytes32[] proof` that `MerkleProof.verify` takes
+/// let proof = tree.gen_proof("c").unwrap();
+/// let leaf = tree.get_leaf_hash("c");
+/// assert_eq!(&process_proof(&leaf, &proof, &Keccak256Hasher::new()), tree.root_hash());
+/// ```
+#[derive(Clone)]
+pub struct StandardMerkleTree<H: Hasher> {
+    pub hasher: H,
+    /// Every node of the tree, the root first and the leaves last.
+    heap: Vec<Hash>,
+}
+
+impl<H: Hasher> StandardMerkleTree<H> {
+    pub fn create<T: HashableData>(data: &[T], hasher: H) -> Result<Self> {
+        let mut leaf_hashes: Vec<Hash> = data
+            .iter()
+            .map(|el| hasher.get_leaf_hash(el, HashingMode::SortedPair))
+            .collect();
+        leaf_hashes.sort_unstable();
+        StandardMerkleTree::create_from_leaf_hashes(leaf_hashes, hasher)
+    }
+
+    /// Builds the tree from leaves that are already hashed, keeping their order, as
+    /// `StandardMerkleTree.of` does with `sortLeaves: false`.
+    pub fn create_from_leaf_hashes(leaf_hashes: Vec<Hash>, hasher: H) -> Result<Self> {
+        if leaf_hashes.is_empty() {
+            return Err(MerkleError::EmptyInput);
+        }
+
+        let len = leaf_hashes.len();
+        let mut heap = vec![Vec::new(); 2 * len - 1];
+        for (idx, leaf) in leaf_hashes.into_iter().enumerate() {
+            heap[2 * len - 2 - idx] = leaf;
+        }
+        for idx in (0..len - 1).rev() {
+            heap[idx] = hasher.get_node_hash(
+                &heap[2 * idx + 1],
+                &heap[2 * idx + 2],
+                HashingMode::SortedPair,
+            );
+        }
+        Ok(Self { hasher, heap })
+    }
+
+    pub fn len(&self) -> usize {
+        self.heap.len().div_ceil(2)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    pub fn root_hash(&self) -> &Hash {
+        &self.heap[0]
+    }
+
+    /// Hashes `data` the way this tree hashes its leaves.
+    pub fn get_leaf_hash<T: HashableData>(&self, data: T) -> Hash {
+        self.hasher.get_leaf_hash(data, HashingMode::SortedPair)
+    }
+
+    /// The leaf at `idx` in the order they were sorted in.
+    pub fn get_leaf(&self, idx: usize) -> Option<&Hash> {
+        self.get_heap_idx(idx).map(|heap_idx| &self.heap[heap_idx])
+    }
+
+    /// The sibling hashes from the leaf at `idx` up to the root.
+    pub fn gen_proof_by_idx(&self, idx: usize) -> Result<Vec<Hash>> {
+        let mut heap_idx = self.get_heap_idx(idx).ok_or(MerkleError::IndexOutOfRange {
+            index: idx,
+            len: self.len(),
+        })?;
+
+        let mut proof = Vec::new();
+        while heap_idx > 0 {
+            let sibling = if is_even(heap_idx) {
+                heap_idx - 1
+            } else {
+                heap_idx + 1
+            };
+            proof.push(self.heap[sibling].clone());
+            heap_idx = (heap_idx - 1) / 2;
+        }
+        Ok(proof)
+    }
+
+    /// The sibling hashes from the leaf of `data` up to the root, or `None` if it isn't in
+    /// the tree.
+    pub fn gen_proof<T: HashableData>(&self, data: T) -> Option<Vec<Hash>> {
+        let leaf_hash = self.get_leaf_hash(data);
+        let idx = (0..self.len()).find(|idx| self.get_leaf(*idx) == Some(&leaf_hash))?;
+        self.gen_proof_by_idx(idx).ok()
+    }
+
+    /// Checks that hashing `leaf_hash` up `proof` yields the root.
+    pub fn verify_proof(&self, leaf_hash: &Hash, proof: &[Hash]) -> Result<()> {
+        if &process_proof(leaf_hash, proof, &self.hasher) != self.root_hash() {
+            return Err(MerkleError::RootMismatch);
+        }
+        Ok(())
+    }
+
+    fn get_heap_idx(&self, idx: usize) -> Option<usize> {
+        (idx < self.len()).then(|| self.heap.len() - 1 - idx)
+    }
+}
+
+/// The root `proof` leads to from `leaf_hash`, as `MerkleProof.processProof` from
+/// OpenZeppelin's contracts computes it.
+pub fn process_proof<H: Hasher>(leaf_hash: &Hash, proof: &[Hash], hasher: &H) -> Hash {
+    proof.iter().fold(leaf_hash.clone(), |computed, sibling| {
+        hasher.get_node_hash(&computed, sibling, HashingMode::SortedPair)
+    })
+}
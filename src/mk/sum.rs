@@ -0,0 +1,256 @@
+use crate::error::{MerkleError, Result};
+use crate::utils::{crypto::*, num::is_even};
+
+use super::proof::{check_algorithm, check_hash_lengths, check_hashing_mode};
+
+/// A node of a `MerkleSumTree`: the hash of its subtree and the sum of its balances.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SumNode {
+    pub hash: Hash,
+    pub sum: u64,
+}
+
+impl SumNode {
+    /// The leaf holding `data` with `balance`, whose hash commits to both.
+    pub fn leaf<H: Hasher, T: HashableData>(
+        data: T,
+        balance: u64,
+        hasher: &H,
+        hashing_mode: HashingMode,
+    ) -> Self {
+        let data = data.as_ref();
+        let mut el = Vec::with_capacity(data.len() + 8);
+        el.extend_from_slice(data);
+        el.extend_from_slice(&balance.to_be_bytes());
+
+        Self {
+            hash: hasher.get_leaf_hash(el, hashing_mode),
+            sum: balance,
+        }
+    }
+
+    /// The parent of `left` and `right`, failing if their sums overflow or if `hashing_mode`
+    /// is `SortedPair`.
+    pub fn parent<H: Hasher>(
+        left: &SumNode,
+        right: &SumNode,
+        hasher: &H,
+        hashing_mode: HashingMode,
+    ) -> Result<Self> {
+        check_hashing_mode(hashing_mode)?;
+        let sum = left
+            .sum
+            .checked_add(right.sum)
+            .ok_or(MerkleError::SumOverflow)?;
+
+        // the sums are hashed along with the hashes so they can't be changed on the way up
+        let mut data = Vec::with_capacity(left.hash.len() + right.hash.len() + 17);
+        if hashing_mode == HashingMode::DomainSeparated {
+            data.push(NODE_PREFIX);
+        }
+        for child in [left, right] {
+            data.extend_from_slice(&child.hash);
+            data.extend_from_slice(&child.sum.to_be_bytes());
+        }
+
+        Ok(Self {
+            hash: hasher.get_hash_from_data(data),
+            sum,
+        })
+    }
+}
+
+/// Whether the node at `idx` is the last of a level of `level_size` and has no sibling, in
+/// which case it moves up to the next level unchanged.
+fn is_lone(idx: usize, level_size: usize) -> bool {
+    idx + 1 == level_size && is_even(idx)
+}
+
+/// A binary Merkle tree whose nodes commit to the sum of the balances below them, as used in
+/// proofs of liabilities.
+///
+/// Balances are unsigned, so no leaf can make up for another, and every inclusion proof
+/// exposes the sums along its path: a user checking their own balance also checks that the
+/// root sum is made of it. A node without sibling moves up unchanged, so no balance is counted
+/// twice.
+#[derive(Clone)]
+pub struct MerkleSumTree<H: Hasher> {
+    pub hasher: H,
+    pub hashing_mode: HashingMode,
+    /// Every node by level, `levels[0]` being the leaves and the last one the root.
+    levels: Vec<Vec<SumNode>>,
+}
+
+impl<H: Hasher> MerkleSumTree<H> {
+    /// Builds the tree from `(data, balance)` entries, failing if there are none or if the
+    /// balances add up to more than `u64::MAX`.
+    pub fn create<T: HashableData>(entries: &[(T, u64)], hasher: H) -> Result<Self> {
+        MerkleSumTree::create_with_hashing_mode(entries, hasher, HashingMode::default())
+    }
+
+    /// Builds the tree with `Plain` or `DomainSeparated` hashing; `SortedPair` is rejected.
+    pub fn create_with_hashing_mode<T: HashableData>(
+        entries: &[(T, u64)],
+        hasher: H,
+        hashing_mode: HashingMode,
+    ) -> Result<Self> {
+        if entries.is_empty() {
+            return Err(MerkleError::EmptyInput);
+        }
+        check_hashing_mode(hashing_mode)?;
+
+        let leaves: Vec<SumNode> = entries
+            .iter()
+            .map(|(data, balance)| SumNode::leaf(data, *balance, &hasher, hashing_mode))
+            .collect();
+        let mut levels = vec![leaves];
+        while levels.last().unwrap().len() > 1 {
+            let parents = levels
+                .last()
+                .unwrap()
+                .chunks(2)
+                .map(|children| match children {
+                    [left, right] => SumNode::parent(left, right, &hasher, hashing_mode),
+                    lone => Ok(lone[0].clone()),
+                })
+                .collect::<Result<Vec<SumNode>>>()?;
+            levels.push(parents);
+        }
+
+        Ok(Self {
+            hasher,
+            hashing_mode,
+            levels,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.levels[0].len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.levels[0].is_empty()
+    }
+
+    /// The root, whose sum is the total of every balance.
+    pub fn root(&self) -> &SumNode {
+        &self.levels.last().unwrap()[0]
+    }
+
+    /// The sum of every balance.
+    pub fn total(&self) -> u64 {
+        self.root().sum
+    }
+
+    pub fn get_leaf(&self, idx: usize) -> Option<&SumNode> {
+        self.levels[0].get(idx)
+    }
+
+    /// The leaf holding `data` with `balance`, hashed the way this tree hashes its leaves.
+    pub fn get_leaf_node<T: HashableData>(&self, data: T, balance: u64) -> SumNode {
+        SumNode::leaf(data, balance, &self.hasher, self.hashing_mode)
+    }
+
+    /// Generates an inclusion proof for the leaf at `leaf_idx`, with the sum of every sibling.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use merkle_tree::mk::sum::MerkleSumTree;
+    /// use merkle_tree::utils::crypto::Sha256Hasher;
+    ///
+    /// let balances = [("alice", 100), ("bob", 25), ("carol", 7)];
+    /// let tree = MerkleSumTree::create(&balances, Sha256Hasher::new()).unwrap();
+    /// assert_eq!(tree.total(), 132);
+    ///
+    /// let proof = tree.gen_proof(1).unwrap();
+    /// let leaf = tree.get_leaf_node("bob", 25);
+    /// assert!(proof.verify(tree.root(), &leaf, &tree.hasher).is_ok());
+    /// ```
+    pub fn gen_proof(&self, leaf_idx: usize) -> Result<SumProof> {
+        if leaf_idx >= self.len() {
+            return Err(MerkleError::IndexOutOfRange {
+                index: leaf_idx,
+                len: self.len(),
+            });
+        }
+
+        let mut path = Vec::new();
+        let mut idx = leaf_idx;
+        for level in &self.levels[..self.levels.len() - 1] {
+            if !is_lone(idx, level.len()) {
+                path.push(level[idx ^ 1].clone());
+            }
+            idx /= 2;
+        }
+
+        Ok(SumProof {
+            leaf_idx,
+            tree_size: self.len(),
+            algorithm: self.hasher.algorithm(),
+            hashing_mode: self.hashing_mode,
+            path,
+        })
+    }
+}
+
+/// Proves that a leaf and its balance are part of a `MerkleSumTree` of `tree_size` leaves.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SumProof {
+    pub leaf_idx: usize,
+    pub tree_size: usize,
+    pub algorithm: HashAlgorithm,
+    pub hashing_mode: HashingMode,
+    /// Siblings from the leaf level up, with their sums. Levels where the node has no sibling
+    /// are skipped.
+    pub path: Vec<SumNode>,
+}
+
+impl SumProof {
+    /// Recomputes the root from `leaf`, failing if the proof doesn't fit `tree_size` or if the
+    /// sums overflow.
+    pub fn compute_root<H: Hasher>(&self, leaf: &SumNode, hasher: &H) -> Result<SumNode> {
+        check_algorithm(hasher, self.algorithm)?;
+        if self.leaf_idx >= self.tree_size {
+            return Err(MerkleError::IndexOutOfRange {
+                index: self.leaf_idx,
+                len: self.tree_size,
+            });
+        }
+
+        let mut steps = Vec::new();
+        let (mut idx, mut level_size) = (self.leaf_idx, self.tree_size);
+        while level_size > 1 {
+            if !is_lone(idx, level_size) {
+                steps.push(is_even(idx));
+            }
+            idx /= 2;
+            level_size = level_size.div_ceil(2);
+        }
+        if steps.len() != self.path.len() {
+            return Err(MerkleError::ProofLengthMismatch {
+                expected: steps.len(),
+                actual: self.path.len(),
+            });
+        }
+        check_hash_lengths(leaf.hash.len(), self.path.iter().map(|node| &node.hash))?;
+
+        let mut node = leaf.clone();
+        for (is_left, sibling) in steps.into_iter().zip(&self.path) {
+            node = match is_left {
+                true => SumNode::parent(&node, sibling, hasher, self.hashing_mode)?,
+                false => SumNode::parent(sibling, &node, hasher, self.hashing_mode)?,
+            };
+        }
+
+        Ok(node)
+    }
+
+    /// Verifies the proof against a known `root`, its hash and its sum.
+    pub fn verify<H: Hasher>(&self, root: &SumNode, leaf: &SumNode, hasher: &H) -> Result<()> {
+        if self.compute_root(leaf, hasher)? != *root {
+            return Err(MerkleError::RootMismatch);
+        }
+        Ok(())
+    }
+}
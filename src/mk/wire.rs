@@ -0,0 +1,193 @@
+//! A stable binary encoding of `MerkleProof`, to send proofs between services.
+//!
+//! Every integer is big-endian. The encoding starts with a header of `HEADER_LEN` bytes:
+//!
+//! | offset | size | field                                                     |
+//! |--------|------|-----------------------------------------------------------|
+//! | 0      | 1    | version, `VERSION`                                        |
+//! | 1      | 1    | hash algorithm, `HashAlgorithm::id`                       |
+//! | 2      | 1    | hashing mode: 0 plain, 1 domain separated, 2 sorted pair  |
+//! | 3      | 1    | odd node policy: 0 duplicate, 1 promote, 2 pad            |
+//! | 4      | 2    | branching factor                                          |
+//! | 6      | 2    | hash length, 0 when the path has no siblings              |
+//! | 8      | 8    | leaf index                                                |
+//! | 16     | 8    | tree size                                                 |
+//! | 24     | 8    | path bitmap, bit `i` set when the node of level `i` isn't |
+//! |        |      | the first child of its parent                             |
+//!
+//! followed by the siblings of every level, from the leaf up and left to right, each of them
+//! hash length bytes long. Their number and their exact positions follow from the index, the
+//! tree size and the options, so decoding rejects encodings whose bitmap or length doesn't
+//! match them.
+
+use crate::error::{MerkleError, Result};
+use crate::utils::crypto::{Hash, HashAlgorithm, HashingMode};
+
+use super::{
+    options::{OddNodePolicy, TreeOptions},
+    proof::{check_hash_lengths, check_leaf, MerkleProof},
+};
+
+/// The version of the encoding written by `MerkleProof::encode`.
+pub const VERSION: u8 = 1;
+/// The length of the header that comes before the siblings.
+pub const HEADER_LEN: usize = 32;
+
+impl MerkleProof {
+    /// Encodes the proof in the binary format described in the `wire` module.
+    ///
+    /// Fails if the path doesn't have the shape of the path of the leaf, if its siblings
+    /// aren't all as long or with `MerkleError::InvalidEncoding` if the branching factor or
+    /// the hash length don't fit in two bytes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use merkle_tree::mk::compact::CompactMerkleTree;
+    /// use merkle_tree::mk::mk::MerkleTree;
+    /// use merkle_tree::mk::proof::MerkleProof;
+    /// use merkle_tree::utils::crypto::Sha256Hasher;
+    ///
+    /// let data = vec!["hello", "how", "are", "you"];
+    /// let tree = CompactMerkleTree::create(&data, Sha256Hasher::new()).unwrap();
+    /// let proof = tree.gen_proof(2).unwrap();
+    ///
+    /// let bytes = proof.encode().unwrap();
+    /// // the header and two siblings of 32 bytes
+    /// assert_eq!(bytes.len(), 32 + 2 * 32);
+    /// assert_eq!(MerkleProof::decode(&bytes).unwrap(), proof);
+    /// ```
+    pub fn encode(&self) -> Result<Vec<u8>> {
+        self.check_shape()?;
+        let siblings = self.sibling_hashes();
+        let hash_len = siblings.first().map_or(0, |hash| hash.len());
+        check_hash_lengths(hash_len, &siblings)?;
+        let branching_factor = u16::try_from(self.options.branching_factor)
+            .map_err(|_| MerkleError::InvalidEncoding)?;
+        let encoded_hash_len = u16::try_from(hash_len).map_err(|_| MerkleError::InvalidEncoding)?;
+
+        let mut bytes = Vec::with_capacity(HEADER_LEN + siblings.len() * hash_len);
+        bytes.push(VERSION);
+        bytes.push(self.algorithm.id());
+        bytes.push(hashing_mode_id(self.options.hashing_mode));
+        bytes.push(odd_node_policy_id(self.options.odd_node_policy));
+        bytes.extend(branching_factor.to_be_bytes());
+        bytes.extend(encoded_hash_len.to_be_bytes());
+        bytes.extend((self.leaf_idx as u64).to_be_bytes());
+        bytes.extend((self.tree_size as u64).to_be_bytes());
+        bytes.extend(self.get_path_bitmap().to_be_bytes());
+        for sibling in &siblings {
+            bytes.extend(sibling);
+        }
+        Ok(bytes)
+    }
+
+    /// Decodes a proof encoded with `encode`.
+    ///
+    /// Only a proof with the exact length, bitmap and siblings of the path of its leaf is
+    /// accepted, the bytes being otherwise truncated, padded or tampered with. It still has
+    /// to be verified against the expected root.
+    pub fn decode(bytes: &[u8]) -> Result<Self> {
+        let header: &[u8; HEADER_LEN] = bytes
+            .get(..HEADER_LEN)
+            .and_then(|header| header.try_into().ok())
+            .ok_or(MerkleError::EncodingLengthMismatch {
+                expected: HEADER_LEN,
+                actual: bytes.len(),
+            })?;
+        if header[0] != VERSION {
+            return Err(MerkleError::UnsupportedVersion { version: header[0] });
+        }
+        let algorithm = HashAlgorithm::from_id(header[1]).ok_or(MerkleError::InvalidEncoding)?;
+        let options = TreeOptions {
+            hashing_mode: hashing_mode_from_id(header[2]).ok_or(MerkleError::InvalidEncoding)?,
+            odd_node_policy: odd_node_policy_from_id(header[3])
+                .ok_or(MerkleError::InvalidEncoding)?,
+            branching_factor: u16::from_be_bytes([header[4], header[5]]) as usize,
+        };
+        let hash_len = u16::from_be_bytes([header[6], header[7]]) as usize;
+        let leaf_idx = read_usize(&header[8..16])?;
+        let tree_size = read_usize(&header[16..24])?;
+        let bitmap = u64::from_be_bytes(header[24..32].try_into().unwrap());
+
+        // the header alone gives the shape of the path, and so the length of the encoding
+        check_leaf(leaf_idx, tree_size, &options)?;
+        let shape = MerkleProof::expected_shape(leaf_idx, tree_size, &options);
+        let sibling_count: usize = shape.iter().map(|(_, len)| len - 1).sum();
+        if (sibling_count == 0) != (hash_len == 0) {
+            return Err(MerkleError::InvalidEncoding);
+        }
+        let expected_len = sibling_count
+            .checked_mul(hash_len)
+            .and_then(|len| len.checked_add(HEADER_LEN))
+            .ok_or(MerkleError::InvalidEncoding)?;
+        if bytes.len() != expected_len {
+            return Err(MerkleError::EncodingLengthMismatch {
+                expected: expected_len,
+                actual: bytes.len(),
+            });
+        }
+
+        let siblings: Vec<Hash> = bytes[HEADER_LEN..]
+            .chunks(hash_len.max(1))
+            .map(|sibling| sibling.to_vec())
+            .collect();
+        let proof = MerkleProof::new(leaf_idx, tree_size, algorithm, options, siblings);
+        let mismatches = proof.get_path_bitmap() ^ bitmap;
+        if mismatches != 0 {
+            return Err(MerkleError::PositionMismatch {
+                step: mismatches.trailing_zeros() as usize,
+            });
+        }
+        Ok(proof)
+    }
+
+    /// The bits set for the levels where the node isn't the first child of its parent.
+    fn get_path_bitmap(&self) -> u64 {
+        // a tree of at most `u64::MAX` leaves has at most 64 levels under the root
+        self.path
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| node.position != 0)
+            .fold(0, |bitmap, (level, _)| bitmap | 1 << level)
+    }
+}
+
+fn read_usize(bytes: &[u8]) -> Result<usize> {
+    let value = u64::from_be_bytes(bytes.try_into().unwrap());
+    usize::try_from(value).map_err(|_| MerkleError::InvalidEncoding)
+}
+
+fn hashing_mode_id(mode: HashingMode) -> u8 {
+    match mode {
+        HashingMode::Plain => 0,
+        HashingMode::DomainSeparated => 1,
+        HashingMode::SortedPair => 2,
+    }
+}
+
+fn hashing_mode_from_id(id: u8) -> Option<HashingMode> {
+    match id {
+        0 => Some(HashingMode::Plain),
+        1 => Some(HashingMode::DomainSeparated),
+        2 => Some(HashingMode::SortedPair),
+        _ => None,
+    }
+}
+
+fn odd_node_policy_id(policy: OddNodePolicy) -> u8 {
+    match policy {
+        OddNodePolicy::Duplicate => 0,
+        OddNodePolicy::Promote => 1,
+        OddNodePolicy::PadToPowerOfTwo => 2,
+    }
+}
+
+fn odd_node_policy_from_id(id: u8) -> Option<OddNodePolicy> {
+    match id {
+        0 => Some(OddNodePolicy::Duplicate),
+        1 => Some(OddNodePolicy::Promote),
+        2 => Some(OddNodePolicy::PadToPowerOfTwo),
+        _ => None,
+    }
+}
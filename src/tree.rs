@@ -1,73 +1,78 @@
-use std::{
-    cell::RefCell,
-    rc::{Rc, Weak},
-};
+use std::ops::{Index, IndexMut};
 
-pub type NodeParent<T> = Weak<RefCell<Node<T>>>;
-pub type NodeChildren<T> = Vec<Rc<RefCell<Node<T>>>>;
-pub type NodeSiblings<T> = Vec<Weak<RefCell<Node<T>>>>;
+/// The position of a node in its `Arena`.
+pub type NodeId = usize;
 
+/// A node of an `Arena`, linked to its parent and children by their ids.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Node<T> {
     pub value: T,
-    pub parent: Option<NodeParent<T>>,
-    pub children: Option<NodeChildren<T>>,
-    pub siblings: Option<NodeSiblings<T>>,
+    pub parent: Option<NodeId>,
+    /// The children in order. A child may be listed more than once, when a tree pads a group
+    /// with copies of its last node.
+    pub children: Vec<NodeId>,
 }
 
-pub type TreeNode<T> = Rc<RefCell<Node<T>>>;
+/// Stores the nodes of a tree next to each other, so links are indices instead of pointers
+/// and the whole tree is a single allocation.
+#[derive(Debug, Clone, Default)]
+pub struct Arena<T> {
+    nodes: Vec<Node<T>>,
+}
 
-impl<T> Node<T> {
-    pub fn new(
-        value: T,
-        children: Option<NodeChildren<T>>,
-        siblings: Option<NodeSiblings<T>>,
-        parent: Option<NodeParent<T>>,
-    ) -> TreeNode<T> {
-        Rc::new(RefCell::new(Node {
-            children,
-            parent,
-            siblings,
-            value,
-        }))
+impl<T> Arena<T> {
+    pub fn new() -> Self {
+        Self { nodes: Vec::new() }
     }
 
-    pub fn clone<A: Clone>(a: &TreeNode<A>) -> TreeNode<A> {
-        let a = a.borrow();
-        Node::new(
-            a.value.clone(),
-            a.children.clone(),
-            a.siblings.clone(),
-            a.parent.clone(),
-        )
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            nodes: Vec::with_capacity(capacity),
+        }
     }
 
-    pub fn set_parent(parent: &TreeNode<T>, node: &TreeNode<T>) {
-        node.borrow_mut().parent = Some(Rc::downgrade(parent));
+    pub fn len(&self) -> usize {
+        self.nodes.len()
     }
 
-    pub fn set_parent_and_siblings(parent: &TreeNode<T>, siblings: &[&TreeNode<T>]) {
-        for sibling in siblings {
-            let mut sibling_mut = sibling.borrow_mut();
-            sibling_mut.parent = Some(Rc::downgrade(parent));
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
 
-            let mut new_siblings = Vec::new();
-            for other_sibling in siblings {
-                if Rc::ptr_eq(sibling, other_sibling) {
-                    continue;
-                }
-                new_siblings.push(Rc::downgrade(other_sibling));
-            }
-            sibling_mut.siblings = Some(new_siblings);
+    /// Adds a node with `children`, which get it as their parent, and returns its id.
+    pub fn push(&mut self, value: T, children: Vec<NodeId>) -> NodeId {
+        let id = self.nodes.len();
+        for child in &children {
+            self.nodes[*child].parent = Some(id);
         }
+        self.nodes.push(Node {
+            value,
+            parent: None,
+            children,
+        });
+        id
     }
 
-    pub fn get_sibling(&self, idx: usize) -> Option<TreeNode<T>> {
-        self.siblings
-            .as_ref()
-            .and_then(|siblings| siblings.get(idx).and_then(|weak| weak.upgrade()))
+    pub fn get(&self, id: NodeId) -> Option<&Node<T>> {
+        self.nodes.get(id)
     }
 
-    pub fn get_parent(&self) -> Option<TreeNode<T>> {
-        self.parent.as_ref().and_then(|el| el.upgrade())
+    /// The node at `id` and its ancestors, up to the root.
+    pub fn get_path(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        std::iter::successors(Some(id), |id| self.nodes[*id].parent)
+    }
+}
+
+impl<T> Index<NodeId> for Arena<T> {
+    type Output = Node<T>;
+
+    fn index(&self, id: NodeId) -> &Node<T> {
+        &self.nodes[id]
+    }
+}
+
+impl<T> IndexMut<NodeId> for Arena<T> {
+    fn index_mut(&mut self, id: NodeId) -> &mut Node<T> {
+        &mut self.nodes[id]
     }
 }
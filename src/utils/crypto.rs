@@ -1,11 +1,69 @@
 use blake2::{Blake2s256, Digest};
 use sha2::Sha256;
+use sha3::Keccak256;
 
 pub type Hash = Vec<u8>;
 
 pub trait HashableData: AsRef<[u8]> {}
 impl<T: AsRef<[u8]>> HashableData for T {}
 
+/// Identifies the hash function behind a `Hasher`, so proofs can say what they were built with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(u8)]
+pub enum HashAlgorithm {
+    Custom = 0,
+    Sha256 = 1,
+    Blake2s256 = 2,
+    Keccak256 = 3,
+    DoubleSha256 = 4,
+}
+
+impl HashAlgorithm {
+    pub fn id(&self) -> u8 {
+        *self as u8
+    }
+
+    pub fn from_id(id: u8) -> Option<Self> {
+        match id {
+            0 => Some(HashAlgorithm::Custom),
+            1 => Some(HashAlgorithm::Sha256),
+            2 => Some(HashAlgorithm::Blake2s256),
+            3 => Some(HashAlgorithm::Keccak256),
+            4 => Some(HashAlgorithm::DoubleSha256),
+            _ => None,
+        }
+    }
+}
+
+/// Prepended to leaf data when hashing with `HashingMode::DomainSeparated`.
+pub const LEAF_PREFIX: u8 = 0x00;
+/// Prepended to the children of a node when hashing with `HashingMode::DomainSeparated`.
+pub const NODE_PREFIX: u8 = 0x01;
+
+/// How leaves and interior nodes are told apart when hashing them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum HashingMode {
+    /// Leaves go through `get_hash_from_data` and nodes through `get_combined_hash`, or
+    /// `get_hash_from_data` over their concatenated children when they have more than two.
+    ///
+    /// An interior node can be presented as a leaf (second-preimage attack), so only use it
+    /// to reproduce roots computed this way.
+    Plain,
+    /// Leaves are hashed as `H(0x00 || data)` and nodes as `H(0x01 || a || b)`, as in RFC 6962.
+    /// Nodes with more children get the same prefix before all of them.
+    #[default]
+    DomainSeparated,
+    /// Leaves are hashed twice as `H(H(data))` and nodes as `H(min(a, b) || max(a, b))`, as
+    /// OpenZeppelin's `StandardMerkleTree` and `MerkleProof` do. Nodes with more children have
+    /// them sorted before being concatenated.
+    ///
+    /// Pair hashing is commutative, so a proof doesn't need the position of the leaf to be
+    /// checked on-chain.
+    SortedPair,
+}
+
 /// Trait defining operations for generating hashes and combined hashes.
 pub trait Hasher {
     /// Computes the combined hash of two hash values `a` and `b`.
@@ -22,8 +80,7 @@ pub trait Hasher {
     /// # Examples
     ///
     /// ```
-    /// use my_crate::Hasher;
-    /// use my_crate::utils::crypto::Hash;
+    /// use merkle_tree::utils::crypto::{Hash, HashableData, Hasher};
     ///
     /// struct MyHasher;
     ///
@@ -32,6 +89,10 @@ pub trait Hasher {
     ///         // Implement your combined hash logic here
     ///         vec![]
     ///     }
+    ///
+    ///     fn get_hash_from_data<T: HashableData>(&self, el: T) -> Hash {
+    ///         vec![]
+    ///     }
     /// }
     ///
     /// let hasher = MyHasher {};
@@ -55,12 +116,15 @@ pub trait Hasher {
     /// # Examples
     ///
     /// ```
-    /// use my_crate::Hasher;
-    /// use my_crate::utils::crypto::{Hash, HashableData};
+    /// use merkle_tree::utils::crypto::{Hash, HashableData, Hasher};
     ///
     /// struct MyHasher;
     ///
     /// impl Hasher for MyHasher {
+    ///     fn get_combined_hash(&self, a: &Hash, b: &Hash) -> Hash {
+    ///         vec![]
+    ///     }
+    ///
     ///     fn get_hash_from_data<T: HashableData>(&self, el: T) -> Hash {
     ///         // Implement your data hashing logic here
     ///         vec![]
@@ -73,8 +137,68 @@ pub trait Hasher {
     /// println!("Hash: {:?}", hash);
     /// ```
     fn get_hash_from_data<T: HashableData>(&self, el: T) -> Hash;
+
+    /// Computes the hash of a leaf holding `el` under the given `mode`.
+    fn get_leaf_hash<T: HashableData>(&self, el: T, mode: HashingMode) -> Hash {
+        match mode {
+            HashingMode::Plain => self.get_hash_from_data(el),
+            HashingMode::DomainSeparated => {
+                let el = el.as_ref();
+                let mut data = Vec::with_capacity(el.len() + 1);
+                data.push(LEAF_PREFIX);
+                data.extend_from_slice(el);
+                self.get_hash_from_data(data)
+            }
+            HashingMode::SortedPair => self.get_hash_from_data(self.get_hash_from_data(el)),
+        }
+    }
+
+    /// Computes the hash of an interior node with children `a` and `b` under the given `mode`.
+    fn get_node_hash(&self, a: &Hash, b: &Hash, mode: HashingMode) -> Hash {
+        match mode {
+            HashingMode::Plain => self.get_combined_hash(a, b),
+            HashingMode::DomainSeparated => {
+                let mut data = Vec::with_capacity(a.len() + b.len() + 1);
+                data.push(NODE_PREFIX);
+                data.extend_from_slice(a);
+                data.extend_from_slice(b);
+                self.get_hash_from_data(data)
+            }
+            HashingMode::SortedPair => {
+                let (a, b) = if a <= b { (a, b) } else { (b, a) };
+                self.get_hash_from_data([a.as_slice(), b.as_slice()].concat())
+            }
+        }
+    }
+
+    /// Computes the hash of an interior node with any number of `children` under the given
+    /// `mode`. Two children are hashed with `get_node_hash`, more are concatenated.
+    fn get_children_hash(&self, children: &[Hash], mode: HashingMode) -> Hash {
+        if let [a, b] = children {
+            return self.get_node_hash(a, b, mode);
+        }
+        let mut children: Vec<&Hash> = children.iter().collect();
+        if mode == HashingMode::SortedPair {
+            children.sort_unstable();
+        }
+        let mut data =
+            Vec::with_capacity(children.iter().map(|child| child.len()).sum::<usize>() + 1);
+        if mode == HashingMode::DomainSeparated {
+            data.push(NODE_PREFIX);
+        }
+        for child in children {
+            data.extend_from_slice(child);
+        }
+        self.get_hash_from_data(data)
+    }
+
+    /// The algorithm this hasher implements. Custom hashers don't need to override it.
+    fn algorithm(&self) -> HashAlgorithm {
+        HashAlgorithm::Custom
+    }
 }
 
+#[derive(Clone, Default)]
 pub struct Sha256Hasher {}
 
 impl Sha256Hasher {
@@ -93,8 +217,13 @@ impl Hasher for Sha256Hasher {
     fn get_hash_from_data<T: HashableData>(&self, el: T) -> Hash {
         Sha256::new_with_prefix(el).finalize().to_vec()
     }
+
+    fn algorithm(&self) -> HashAlgorithm {
+        HashAlgorithm::Sha256
+    }
 }
 
+#[derive(Clone, Default)]
 pub struct Blake2s256Hasher {}
 
 impl Hasher for Blake2s256Hasher {
@@ -107,6 +236,10 @@ impl Hasher for Blake2s256Hasher {
     fn get_hash_from_data<T: HashableData>(&self, el: T) -> Hash {
         Blake2s256::new_with_prefix(el).finalize().to_vec()
     }
+
+    fn algorithm(&self) -> HashAlgorithm {
+        HashAlgorithm::Blake2s256
+    }
 }
 
 impl Blake2s256Hasher {
@@ -114,3 +247,55 @@ impl Blake2s256Hasher {
         Self {}
     }
 }
+
+/// The Keccak-256 used by Ethereum, which isn't the standardized SHA3-256.
+#[derive(Clone, Default)]
+pub struct Keccak256Hasher {}
+
+impl Keccak256Hasher {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl Hasher for Keccak256Hasher {
+    fn get_combined_hash(&self, a: &Hash, b: &Hash) -> Hash {
+        let mut hasher = Keccak256::new_with_prefix(a);
+        hasher.update(b);
+        hasher.finalize().to_vec()
+    }
+
+    fn get_hash_from_data<T: HashableData>(&self, el: T) -> Hash {
+        Keccak256::new_with_prefix(el).finalize().to_vec()
+    }
+
+    fn algorithm(&self) -> HashAlgorithm {
+        HashAlgorithm::Keccak256
+    }
+}
+
+/// SHA-256 applied twice, as Bitcoin hashes transactions and the nodes of their tree.
+#[derive(Clone, Default)]
+pub struct DoubleSha256Hasher {}
+
+impl DoubleSha256Hasher {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl Hasher for DoubleSha256Hasher {
+    fn get_combined_hash(&self, a: &Hash, b: &Hash) -> Hash {
+        let mut hasher = Sha256::new_with_prefix(a);
+        hasher.update(b);
+        Sha256::digest(hasher.finalize()).to_vec()
+    }
+
+    fn get_hash_from_data<T: HashableData>(&self, el: T) -> Hash {
+        Sha256::digest(Sha256::digest(el)).to_vec()
+    }
+
+    fn algorithm(&self) -> HashAlgorithm {
+        HashAlgorithm::DoubleSha256
+    }
+}
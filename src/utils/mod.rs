@@ -1,2 +1,4 @@
 pub mod crypto;
 pub mod num;
+#[cfg(feature = "parallel")]
+pub(crate) mod parallel;
@@ -1,3 +1,3 @@
 pub fn is_even(num: usize) -> bool {
-    num % 2 == 0
+    num.is_multiple_of(2)
 }
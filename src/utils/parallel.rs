@@ -0,0 +1,25 @@
+//! Helpers that spread work across threads with rayon, for the `parallel` feature. They give
+//! the same results in the same order as their serial counterparts.
+
+use rayon::prelude::*;
+
+/// Maps every item with `f`.
+pub(crate) fn map<I, O, F>(items: &[I], f: F) -> Vec<O>
+where
+    I: Sync,
+    O: Send,
+    F: Fn(&I) -> O + Sync + Send,
+{
+    items.par_iter().map(f).collect()
+}
+
+/// Maps every chunk of `size` items with `f`, the last one being shorter if they don't split
+/// evenly.
+pub(crate) fn map_chunks<I, O, F>(items: &[I], size: usize, f: F) -> Vec<O>
+where
+    I: Sync,
+    O: Send,
+    F: Fn(&[I]) -> O + Sync + Send,
+{
+    items.par_chunks(size).map(f).collect()
+}
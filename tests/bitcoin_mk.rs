@@ -0,0 +1,91 @@
+use merkle_tree::mk::compact::CompactMerkleTree;
+use merkle_tree::utils::crypto::*;
+
+#[cfg(test)]
+mod tests {
+    use merkle_tree::error::MerkleError;
+    use merkle_tree::mk::mk::MerkleTree;
+
+    use super::*;
+
+    /// Hashes as explorers show them, reversed to internal byte order.
+    fn from_display_hex(hex: &str) -> Hash {
+        let mut hash: Hash = (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect();
+        hash.reverse();
+        hash
+    }
+
+    /// Blocks as (merkle root, txids), in display order.
+    const BLOCKS: [(&str, &[&str]); 3] = [
+        // genesis, a single transaction is its own root
+        (
+            "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b",
+            &["4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b"],
+        ),
+        // block 170
+        (
+            "7dac2c5666815c17a3b36427de37bb9d2e2c5ccec3f8633eb91a4205cb4c10ff",
+            &[
+                "b1fea52486ce0c62bb442b530a3f0132b826c74e473d1f2c220bfa78111c5082",
+                "f4184fc596403b9d638783cf57adfe4c75c605f6356fbc91338530e9831e9e16",
+            ],
+        ),
+        // block 100000
+        (
+            "f3e94742aca4b5ef85488dc37c06c3282295ffec960994b2c0d5ac2a25a95766",
+            &[
+                "8c14f0db3df150123e6f3dbbf30f8b955a8249b62ac1d1ff16284aefa3d06d87",
+                "fff2525b8931402dd09222c50775608f75787bd2b87e56995a7bdd30f79702c4",
+                "6359f0868171b1d194cbee1af2f16ea598ae8fad666d9b012c8ed2b79a236ec4",
+                "e9a66845e05d5abc0ad04ec80f774a7e585c6e8db975962d069a522137b80c1d",
+            ],
+        ),
+    ];
+
+    #[test]
+    fn test_block_merkle_roots() {
+        for (root, txids) in BLOCKS {
+            let txids: Vec<Hash> = txids.iter().map(|txid| from_display_hex(txid)).collect();
+            let tree = CompactMerkleTree::create_bitcoin(txids).unwrap();
+            assert_eq!(tree.root_hash, from_display_hex(root));
+        }
+    }
+
+    #[test]
+    fn test_spv_proofs() {
+        let (root, txids) = BLOCKS[2];
+        let root = from_display_hex(root);
+        let txids: Vec<Hash> = txids.iter().map(|txid| from_display_hex(txid)).collect();
+        let tree = CompactMerkleTree::create_bitcoin(txids.clone()).unwrap();
+
+        for (idx, txid) in txids.iter().enumerate() {
+            let proof = tree.gen_proof(idx).unwrap();
+            assert_eq!(proof.algorithm, HashAlgorithm::DoubleSha256);
+            assert!(proof
+                .verify(&root, txid, &DoubleSha256Hasher::new())
+                .is_ok());
+        }
+
+        // a transaction of another block isn't in this one
+        let proof = tree.gen_proof(1).unwrap();
+        let other = from_display_hex(BLOCKS[1].1[1]);
+        assert_eq!(
+            proof.verify(&root, &other, &DoubleSha256Hasher::new()),
+            Err(MerkleError::RootMismatch)
+        );
+    }
+
+    #[test]
+    fn test_odd_levels_duplicate_the_last_node() {
+        let hasher = DoubleSha256Hasher::new();
+        let txids: Vec<Hash> = (1..=3).map(|i| hasher.get_hash_from_data([i])).collect();
+        let tree = CompactMerkleTree::create_bitcoin(txids.clone()).unwrap();
+
+        let ab = hasher.get_combined_hash(&txids[0], &txids[1]);
+        let cc = hasher.get_combined_hash(&txids[2], &txids[2]);
+        assert_eq!(tree.root_hash, hasher.get_combined_hash(&ab, &cc));
+    }
+}
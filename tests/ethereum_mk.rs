@@ -0,0 +1,167 @@
+use merkle_tree::mk::options::TreeOptions;
+use merkle_tree::mk::sorted::SortedMerkleTree;
+use merkle_tree::mk::standard::{process_proof, StandardMerkleTree};
+use merkle_tree::utils::crypto::*;
+
+#[cfg(test)]
+mod tests {
+    use merkle_tree::mk::mk::MerkleTree;
+
+    use super::*;
+
+    fn from_hex(hex: &str) -> Hash {
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    /// `abi.encode(address, uint256)`, the leaf values of the vectors.
+    fn abi_encode(address: [u8; 20], amount: u128) -> Vec<u8> {
+        let mut encoded = vec![0; 12];
+        encoded.extend_from_slice(&address);
+        encoded.extend_from_slice(&[0; 16]);
+        encoded.extend_from_slice(&amount.to_be_bytes());
+        encoded
+    }
+
+    fn create(values: &[Vec<u8>]) -> SortedMerkleTree<Keccak256Hasher> {
+        SortedMerkleTree::create_with_options(
+            values,
+            Keccak256Hasher::new(),
+            TreeOptions::openzeppelin(),
+        )
+        .unwrap()
+    }
+
+    fn values(size: usize) -> Vec<Vec<u8>> {
+        (0..size)
+            .map(|i| abi_encode([i as u8 + 1; 20], i as u128 * 1000))
+            .collect()
+    }
+
+    #[test]
+    fn test_keccak256() {
+        let hasher = Keccak256Hasher::new();
+        assert_eq!(hasher.algorithm(), HashAlgorithm::Keccak256);
+        assert_eq!(
+            hasher.get_hash_from_data(""),
+            from_hex("c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470")
+        );
+    }
+
+    #[test]
+    fn test_sorted_pair_is_commutative() {
+        let hasher = Keccak256Hasher::new();
+        let [a, b, c] = ["a", "b", "c"].map(|el| hasher.get_leaf_hash(el, HashingMode::SortedPair));
+        let mode = HashingMode::SortedPair;
+
+        assert_eq!(
+            hasher.get_node_hash(&a, &b, mode),
+            hasher.get_node_hash(&b, &a, mode)
+        );
+        assert_eq!(
+            hasher.get_children_hash(&[a.clone(), b.clone(), c.clone()], mode),
+            hasher.get_children_hash(&[c, a, b], mode)
+        );
+    }
+
+    #[test]
+    fn test_standard_merkle_tree_vector() {
+        // the example of OpenZeppelin's merkle-tree library
+        let values = vec![
+            abi_encode([0x11; 20], 5_000_000_000_000_000_000),
+            abi_encode([0x22; 20], 2_500_000_000_000_000_000),
+        ];
+        let tree = create(&values);
+
+        assert_eq!(
+            tree.root_hash(),
+            &from_hex("d4dee0beab2d53f2cc83e567171bd2820e49898130a22622b10ead383e90bd77")
+        );
+        let standard = StandardMerkleTree::create(&values, Keccak256Hasher::new()).unwrap();
+        assert_eq!(standard.root_hash(), tree.root_hash());
+        for value in &values {
+            let proof = tree.gen_proof(value).unwrap();
+            let leaf = tree.get_leaf_hash(value);
+            assert_eq!(standard.gen_proof(value).unwrap(), proof.sibling_hashes());
+            assert_eq!(
+                &process_proof(&leaf, &proof.sibling_hashes(), &Keccak256Hasher::new()),
+                tree.root_hash()
+            );
+        }
+    }
+
+    #[test]
+    fn test_standard_merkle_tree_layout() {
+        let hash = |a: &Hash, b: &Hash| {
+            Keccak256Hasher::new().get_node_hash(a, b, HashingMode::SortedPair)
+        };
+        let sorted_leaves = |tree: &StandardMerkleTree<Keccak256Hasher>| {
+            let leaves: Vec<Hash> = (0..tree.len())
+                .map(|idx| tree.get_leaf(idx).unwrap().clone())
+                .collect();
+            assert!(leaves.is_sorted());
+            leaves
+        };
+
+        // the heap of 5 leaves: [h(t1, t2), h(t3, l4), h(l3, l2), h(l1, l0), l4, l3, l2, l1, l0]
+        let tree = StandardMerkleTree::create(&values(5), Keccak256Hasher::new()).unwrap();
+        let [l0, l1, l2, l3, l4] = sorted_leaves(&tree).try_into().unwrap();
+        let expected = hash(&hash(&hash(&l1, &l0), &l4), &hash(&l3, &l2));
+        assert_eq!(tree.root_hash(), &expected);
+        assert_ne!(create(&values(5)).root_hash(), &expected);
+
+        // the heap of 7 leaves, the last level holding l0 to l5
+        let tree = StandardMerkleTree::create(&values(7), Keccak256Hasher::new()).unwrap();
+        let [l0, l1, l2, l3, l4, l5, l6] = sorted_leaves(&tree).try_into().unwrap();
+        let expected = hash(
+            &hash(&hash(&l5, &l4), &hash(&l3, &l2)),
+            &hash(&hash(&l1, &l0), &l6),
+        );
+        assert_eq!(tree.root_hash(), &expected);
+        assert_ne!(create(&values(7)).root_hash(), &expected);
+    }
+
+    #[test]
+    fn test_standard_merkle_tree_proofs() {
+        for size in 1..=20 {
+            let values = values(size);
+            let tree = StandardMerkleTree::create(&values, Keccak256Hasher::new()).unwrap();
+            for value in &values {
+                let leaf = tree.get_leaf_hash(value);
+                let proof = tree.gen_proof(value).unwrap();
+                assert!(tree.verify_proof(&leaf, &proof).is_ok());
+                assert_eq!(
+                    &process_proof(&leaf, &proof, &Keccak256Hasher::new()),
+                    tree.root_hash()
+                );
+            }
+            assert!(tree.gen_proof("missing").is_none());
+
+            // promoting lone nodes only pairs them the same way for a power of two leaves or
+            // three times one
+            let same_layout =
+                size.is_power_of_two() || (size % 3 == 0 && (size / 3).is_power_of_two());
+            assert_eq!(create(&values).root_hash() == tree.root_hash(), same_layout);
+        }
+    }
+
+    #[test]
+    fn test_proofs_verify_on_chain() {
+        for size in 1..20 {
+            let values = values(size);
+            let tree = create(&values);
+            for value in &values {
+                let leaf = tree.get_leaf_hash(value);
+                let proof = tree.gen_proof(value).unwrap();
+                assert!(tree.tree().verify_proof(&leaf, &proof).is_ok());
+                // the contract only takes the siblings
+                assert_eq!(
+                    &process_proof(&leaf, &proof.sibling_hashes(), &Keccak256Hasher::new()),
+                    tree.root_hash()
+                );
+            }
+        }
+    }
+}
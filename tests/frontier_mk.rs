@@ -0,0 +1,78 @@
+use merkle_tree::mk::frontier::FrontierMerkleTree;
+use merkle_tree::utils::crypto::*;
+
+#[cfg(test)]
+mod tests {
+    use merkle_tree::error::MerkleError;
+    use merkle_tree::mk::compact::CompactMerkleTree;
+    use merkle_tree::mk::options::{OddNodePolicy, TreeOptions};
+
+    use super::*;
+
+    /// Builds the whole tree, padding the leaves with zero hashes.
+    fn naive_root(hasher: &Sha256Hasher, leaves: &[Hash], depth: usize) -> Hash {
+        let mut nodes = leaves.to_vec();
+        nodes.resize(1 << depth, vec![0; 32]);
+        while nodes.len() > 1 {
+            nodes = nodes
+                .chunks(2)
+                .map(|pair| hasher.get_node_hash(&pair[0], &pair[1], HashingMode::DomainSeparated))
+                .collect();
+        }
+        nodes.remove(0)
+    }
+
+    #[test]
+    fn test_root_after_each_append() {
+        let depth = 4;
+        let mut tree = FrontierMerkleTree::new(depth, Sha256Hasher {});
+        let mut leaves = Vec::new();
+        assert_eq!(tree.root_hash, naive_root(&tree.hasher, &leaves, depth));
+
+        for i in 0..tree.capacity() {
+            tree.append(i.to_string()).unwrap();
+            leaves.push(tree.get_leaf_hash(i.to_string()));
+            assert_eq!(tree.len(), i + 1);
+            assert_eq!(tree.root_hash, naive_root(&tree.hasher, &leaves, depth));
+        }
+    }
+
+    #[test]
+    fn test_full_tree_matches_padded_compact_tree() {
+        let data: Vec<String> = (0..8).map(|i| i.to_string()).collect();
+        let mut tree = FrontierMerkleTree::new(3, Sha256Hasher {});
+        for el in &data {
+            tree.append(el).unwrap();
+        }
+
+        let options = TreeOptions {
+            odd_node_policy: OddNodePolicy::PadToPowerOfTwo,
+            ..Default::default()
+        };
+        let compact =
+            CompactMerkleTree::create_with_options(&data, Sha256Hasher {}, options).unwrap();
+        assert_eq!(tree.root_hash, compact.root_hash);
+    }
+
+    #[test]
+    fn test_append_to_full_tree() {
+        let mut tree = FrontierMerkleTree::new(2, Sha256Hasher {});
+        for i in 0..4 {
+            tree.append([i]).unwrap();
+        }
+        let root = tree.root_hash.clone();
+
+        assert_eq!(
+            tree.append([4]),
+            Err(MerkleError::IndexOutOfRange { index: 4, len: 4 })
+        );
+        assert_eq!(tree.root_hash, root);
+        assert_eq!(tree.len(), 4);
+
+        // a tree without levels takes a single leaf, which is its root
+        let mut tree = FrontierMerkleTree::new(0, Sha256Hasher {});
+        tree.append("hello").unwrap();
+        assert_eq!(tree.root_hash, tree.get_leaf_hash("hello"));
+        assert!(tree.append("world").is_err());
+    }
+}
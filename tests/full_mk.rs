@@ -4,7 +4,10 @@ use merkle_tree::utils::crypto::*;
 #[cfg(test)]
 mod tests {
 
+    use merkle_tree::error::MerkleError;
+    use merkle_tree::mk::compact::CompactMerkleTree;
     use merkle_tree::mk::mk::MerkleTree;
+    use merkle_tree::mk::options::{OddNodePolicy, TreeOptions};
 
     use super::*;
 
@@ -16,15 +19,9 @@ mod tests {
         assert_eq!(tree.leaves.len(), 4);
         assert_eq!(tree.root_hash.len(), 32);
 
-        let expected_root_hash = tree.hasher.get_combined_hash(
-            &tree.hasher.get_combined_hash(
-                &tree.hasher.get_hash_from_data(&data[0]),
-                &tree.hasher.get_hash_from_data(&data[1]),
-            ),
-            &tree.hasher.get_combined_hash(
-                &tree.hasher.get_hash_from_data(&data[2]),
-                &tree.hasher.get_hash_from_data(&data[3]),
-            ),
+        let expected_root_hash = tree.get_node_hash(
+            &tree.get_node_hash(&tree.get_leaf_hash(data[0]), &tree.get_leaf_hash(data[1])),
+            &tree.get_node_hash(&tree.get_leaf_hash(data[2]), &tree.get_leaf_hash(data[3])),
         );
         assert_eq!(tree.root_hash, expected_root_hash);
     }
@@ -37,15 +34,9 @@ mod tests {
         assert_eq!(tree.leaves.len(), 3);
         assert_eq!(tree.root_hash.len(), 32);
 
-        let expected_root_hash = tree.hasher.get_combined_hash(
-            &tree.hasher.get_combined_hash(
-                &tree.hasher.get_hash_from_data(&data[0]),
-                &tree.hasher.get_hash_from_data(&data[1]),
-            ),
-            &tree.hasher.get_combined_hash(
-                &tree.hasher.get_hash_from_data(&data[2]),
-                &tree.hasher.get_hash_from_data(&data[2]),
-            ),
+        let expected_root_hash = tree.get_node_hash(
+            &tree.get_node_hash(&tree.get_leaf_hash(data[0]), &tree.get_leaf_hash(data[1])),
+            &tree.get_node_hash(&tree.get_leaf_hash(data[2]), &tree.get_leaf_hash(data[2])),
         );
         assert_eq!(tree.root_hash, expected_root_hash);
     }
@@ -57,23 +48,17 @@ mod tests {
 
         // test proof for fist leaf
         let proof = vec![
-            tree.hasher.get_hash_from_data(&data[1]),
-            tree.hasher.get_combined_hash(
-                &tree.hasher.get_hash_from_data(&data[2]),
-                &tree.hasher.get_hash_from_data(&data[3]),
-            ),
+            tree.get_leaf_hash(data[1]),
+            tree.get_node_hash(&tree.get_leaf_hash(data[2]), &tree.get_leaf_hash(data[3])),
         ];
-        assert_eq!(proof, tree.gen_proof(0).unwrap());
+        assert_eq!(proof, tree.gen_proof(0).unwrap().sibling_hashes());
 
         // test proof for second leaf
         let proof = vec![
-            tree.hasher.get_hash_from_data(&data[0]),
-            tree.hasher.get_combined_hash(
-                &tree.hasher.get_hash_from_data(&data[2]),
-                &tree.hasher.get_hash_from_data(&data[3]),
-            ),
+            tree.get_leaf_hash(data[0]),
+            tree.get_node_hash(&tree.get_leaf_hash(data[2]), &tree.get_leaf_hash(data[3])),
         ];
-        assert_eq!(proof, tree.gen_proof(1).unwrap());
+        assert_eq!(proof, tree.gen_proof(1).unwrap().sibling_hashes());
     }
 
     #[test]
@@ -83,23 +68,17 @@ mod tests {
 
         // test proof for fist leaf
         let proof = vec![
-            tree.hasher.get_hash_from_data(&data[1]),
-            tree.hasher.get_combined_hash(
-                &tree.hasher.get_hash_from_data(&data[2]),
-                &tree.hasher.get_hash_from_data(&data[2]),
-            ),
+            tree.get_leaf_hash(data[1]),
+            tree.get_node_hash(&tree.get_leaf_hash(data[2]), &tree.get_leaf_hash(data[2])),
         ];
-        assert_eq!(proof, tree.gen_proof(0).unwrap());
+        assert_eq!(proof, tree.gen_proof(0).unwrap().sibling_hashes());
 
         // test proof for second leaf
         let proof = vec![
-            tree.hasher.get_hash_from_data(&data[0]),
-            tree.hasher.get_combined_hash(
-                &tree.hasher.get_hash_from_data(&data[2]),
-                &tree.hasher.get_hash_from_data(&data[2]),
-            ),
+            tree.get_leaf_hash(data[0]),
+            tree.get_node_hash(&tree.get_leaf_hash(data[2]), &tree.get_leaf_hash(data[2])),
         ];
-        assert_eq!(proof, tree.gen_proof(1).unwrap());
+        assert_eq!(proof, tree.gen_proof(1).unwrap().sibling_hashes());
     }
 
     #[test]
@@ -108,11 +87,15 @@ mod tests {
         let tree = FullMerkleTree::create(data.as_slice(), Sha256Hasher {}).unwrap();
 
         // test for first
-        let leaf_hash = &tree.hasher.get_hash_from_data(&data[0]);
-        assert!(&tree.verify_proof(&leaf_hash, 0, tree.gen_proof(0).unwrap()));
-
-        let leaf_hash = &tree.hasher.get_hash_from_data(&data[2]);
-        assert!(&tree.verify_proof(&leaf_hash, 2, tree.gen_proof(2).unwrap()));
+        let leaf_hash = &tree.get_leaf_hash(data[0]);
+        assert!(tree
+            .verify_proof(leaf_hash, &tree.gen_proof(0).unwrap())
+            .is_ok());
+
+        let leaf_hash = &tree.get_leaf_hash(data[2]);
+        assert!(tree
+            .verify_proof(leaf_hash, &tree.gen_proof(2).unwrap())
+            .is_ok());
     }
 
     #[test]
@@ -121,11 +104,15 @@ mod tests {
         let tree = FullMerkleTree::create(data.as_slice(), Sha256Hasher {}).unwrap();
 
         // test for first
-        let leaf_hash = &tree.hasher.get_hash_from_data(&data[0]);
-        assert!(&tree.verify_proof(&leaf_hash, 0, tree.gen_proof(0).unwrap()));
-
-        let leaf_hash = &tree.hasher.get_hash_from_data(&data[2]);
-        assert!(&tree.verify_proof(&leaf_hash, 2, tree.gen_proof(2).unwrap()));
+        let leaf_hash = &tree.get_leaf_hash(data[0]);
+        assert!(tree
+            .verify_proof(leaf_hash, &tree.gen_proof(0).unwrap())
+            .is_ok());
+
+        let leaf_hash = &tree.get_leaf_hash(data[2]);
+        assert!(tree
+            .verify_proof(leaf_hash, &tree.gen_proof(2).unwrap())
+            .is_ok());
     }
 
     #[test]
@@ -133,8 +120,10 @@ mod tests {
         let data = vec!["hello", "how", "are", "you"];
         let tree = FullMerkleTree::create(data.as_slice(), Sha256Hasher {}).unwrap();
 
-        let leaf_hash = &tree.hasher.get_hash_from_data("not right");
-        assert!(!&tree.verify_proof(&leaf_hash, 0, tree.gen_proof(0).unwrap()));
+        let leaf_hash = &tree.get_leaf_hash("not right");
+        assert!(tree
+            .verify_proof(leaf_hash, &tree.gen_proof(0).unwrap())
+            .is_err());
     }
 
     #[test]
@@ -142,8 +131,10 @@ mod tests {
         let data = vec!["how", "are", "you"];
         let tree = FullMerkleTree::create(data.as_slice(), Sha256Hasher {}).unwrap();
 
-        let leaf_hash = &tree.hasher.get_hash_from_data("not right");
-        assert!(!&tree.verify_proof(&leaf_hash, 2, tree.gen_proof(2).unwrap()));
+        let leaf_hash = &tree.get_leaf_hash("not right");
+        assert!(tree
+            .verify_proof(leaf_hash, &tree.gen_proof(2).unwrap())
+            .is_err());
     }
 
     #[test]
@@ -161,18 +152,26 @@ mod tests {
         let mut tree = FullMerkleTree::create(data.as_slice(), Sha256Hasher {}).unwrap();
         assert_eq!(tree.leaves.len(), 4);
 
-        tree.delete_leaf(0);
+        tree.delete_leaf(0).unwrap();
         assert_eq!(tree.leaves.len(), 3)
     }
+
+    #[test]
+    fn test_delete_only_leaf() {
+        let mut tree = FullMerkleTree::create(&["hello"], Sha256Hasher {}).unwrap();
+
+        assert_eq!(tree.delete_leaf(0), Err(MerkleError::EmptyInput));
+        assert_eq!(tree.leaves.len(), 1);
+    }
     #[test]
     fn test_leaf_gets_updated() {
         let data = vec!["hello", "how", "are", "you"];
         let mut tree = FullMerkleTree::create(data.as_slice(), Sha256Hasher {}).unwrap();
         assert_eq!(tree.leaves.len(), 4);
-        tree.update_leaf(0, "hi");
+        tree.update_leaf(0, "hi").unwrap();
 
-        let val = &tree.leaves.get(0).unwrap().borrow().value.clone();
-        assert_eq!(val, &tree.hasher.get_hash_from_data("hi"))
+        let val = &tree.arena()[tree.leaves[0]].value.clone();
+        assert_eq!(val, &tree.get_leaf_hash("hi"))
     }
 
     #[test]
@@ -180,9 +179,9 @@ mod tests {
         let data = vec!["hello", "how", "are", "you"];
         let mut tree = FullMerkleTree::create(data.as_slice(), Sha256Hasher {}).unwrap();
         assert_eq!(tree.leaves.len(), 4);
-        tree.update_leaf(0, "hi");
+        tree.update_leaf(0, "hi").unwrap();
 
-        let res = tree.contains_hash(&tree.hasher.get_hash_from_data("are"));
+        let res = tree.contains_hash(&tree.get_leaf_hash("are"));
         assert_eq!(res.unwrap(), (2, tree.gen_proof(2).unwrap()));
     }
 
@@ -192,18 +191,674 @@ mod tests {
         let tree = FullMerkleTree::create(data.as_slice(), Sha256Hasher {}).unwrap();
 
         let res = tree.get_leaf_by_idx(2);
+        assert_eq!(res.unwrap().value, tree.get_leaf_hash("are"));
+    }
+
+    #[test]
+    fn test_get_leaf_by_hash() {
+        let data = vec!["hello", "how", "are", "you"];
+        let tree = FullMerkleTree::create(data.as_slice(), Sha256Hasher {}).unwrap();
+        let hash = &tree.get_leaf_hash("are");
+        let res = tree.get_leaf_by_hash(hash);
+        assert_eq!(res.unwrap().value, *hash);
+    }
+
+    #[test]
+    fn test_verify_proof_without_tree() {
+        let data = vec!["hello", "how", "are", "you", "doing"];
+        let tree = FullMerkleTree::create(data.as_slice(), Sha256Hasher {}).unwrap();
+        let root = tree.root_hash.clone();
+        let hasher = Sha256Hasher::new();
+
+        for (idx, el) in data.iter().enumerate() {
+            let proof = tree.gen_proof(idx).unwrap();
+            assert!(proof
+                .verify(
+                    &root,
+                    &hasher.get_leaf_hash(el, HashingMode::DomainSeparated),
+                    &hasher
+                )
+                .is_ok());
+        }
+
+        let proof = tree.gen_proof(1).unwrap();
+        assert!(proof
+            .verify(
+                &root,
+                &hasher.get_leaf_hash("hello", HashingMode::DomainSeparated),
+                &hasher
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_proof_carries_shape() {
+        let data = vec!["hello", "how", "are", "you", "doing"];
+        let tree = FullMerkleTree::create(data.as_slice(), Sha256Hasher {}).unwrap();
+
+        let proof = tree.gen_proof(4).unwrap();
+        assert_eq!(proof.leaf_idx, 4);
+        assert_eq!(proof.tree_size, 5);
+        assert_eq!(proof.algorithm, HashAlgorithm::Sha256);
+        let positions: Vec<usize> = proof.path.iter().map(|node| node.position).collect();
+        assert_eq!(positions, vec![0, 0, 1]);
+
+        // a proof claiming another position must not verify
+        let leaf_hash = tree.get_leaf_hash("doing");
+        let mut moved = proof.clone();
+        moved.leaf_idx = 0;
+        assert_eq!(
+            tree.verify_proof(&leaf_hash, &moved),
+            Err(MerkleError::PositionMismatch { step: 2 })
+        );
+
+        // neither should one checked with a different hash function
         assert_eq!(
-            res.unwrap().borrow().value,
-            tree.hasher.get_hash_from_data("are")
+            proof.verify(&tree.root_hash, &leaf_hash, &Blake2s256Hasher::new()),
+            Err(MerkleError::AlgorithmMismatch {
+                expected: HashAlgorithm::Blake2s256,
+                actual: HashAlgorithm::Sha256,
+            })
         );
     }
 
     #[test]
-    fn test_get_leaf_by_hash() {
+    fn test_errors() {
+        let empty: Vec<&str> = vec![];
+        assert_eq!(
+            FullMerkleTree::create(empty.as_slice(), Sha256Hasher {}).err(),
+            Some(MerkleError::EmptyInput)
+        );
+
+        let data = vec!["hello", "how", "are"];
+        let mut tree = FullMerkleTree::create(data.as_slice(), Sha256Hasher {}).unwrap();
+        let out_of_range = Err(MerkleError::IndexOutOfRange { index: 3, len: 3 });
+        assert_eq!(tree.delete_leaf(3), out_of_range);
+        assert_eq!(tree.update_leaf(3, "hi"), out_of_range);
+        assert_eq!(tree.gen_proof(3).err(), out_of_range.err());
+        assert_eq!(
+            tree.gen_consistency_proof(2, 4).err(),
+            Some(MerkleError::InvalidSizes {
+                old_size: 2,
+                new_size: 4,
+                len: 3
+            })
+        );
+        // nothing was touched by the failed calls
+        assert_eq!(tree.leaves.len(), 3);
+
+        let leaf_hash = tree.get_leaf_hash("hello");
+        let proof = tree.gen_proof(0).unwrap();
+        let mut short = proof.clone();
+        short.path.pop();
+        assert_eq!(
+            tree.verify_proof(&leaf_hash, &short),
+            Err(MerkleError::ProofLengthMismatch {
+                expected: 2,
+                actual: 1
+            })
+        );
+        let mut truncated = proof.clone();
+        truncated.path[0].siblings[0].pop();
+        assert_eq!(
+            tree.verify_proof(&leaf_hash, &truncated),
+            Err(MerkleError::HashLengthMismatch {
+                expected: 32,
+                actual: 31
+            })
+        );
+        let mut plain = proof.clone();
+        plain.options.hashing_mode = HashingMode::Plain;
+        assert_eq!(
+            tree.verify_proof(&leaf_hash, &plain),
+            Err(MerkleError::OptionsMismatch)
+        );
+        assert_eq!(
+            tree.verify_proof(&tree.get_leaf_hash("how"), &proof),
+            Err(MerkleError::RootMismatch)
+        );
+    }
+
+    #[test]
+    fn test_multi_proof() {
+        let data = vec!["a", "b", "c", "d", "e", "f", "g"];
+        let tree = FullMerkleTree::create(data.as_slice(), Sha256Hasher {}).unwrap();
+
+        // every non empty subset of leaves
+        for mask in 1..(1usize << data.len()) {
+            let indices: Vec<usize> = (0..data.len()).filter(|i| mask & (1 << i) != 0).collect();
+            let proof = tree.gen_multi_proof(&indices).unwrap();
+            let leaf_hashes: Vec<Hash> = indices
+                .iter()
+                .map(|i| tree.get_leaf_hash(data[*i]))
+                .collect();
+            assert!(tree.verify_multi_proof(&leaf_hashes, &proof).is_ok());
+        }
+
+        // siblings shared by the leaves are only sent once
+        let proof = tree.gen_multi_proof(&[0, 1, 2, 3]).unwrap();
+        assert_eq!(proof.siblings.len(), 1);
+        assert!(tree.gen_multi_proof(&[0, 7]).is_err());
+    }
+
+    #[test]
+    fn test_multi_proof_fails() {
+        let data = vec!["a", "b", "c", "d", "e"];
+        let tree = FullMerkleTree::create(data.as_slice(), Sha256Hasher {}).unwrap();
+        let proof = tree.gen_multi_proof(&[4, 1]).unwrap();
+        assert_eq!(proof.leaf_indices, vec![1, 4]);
+
+        let wrong = vec![tree.get_leaf_hash("b"), tree.get_leaf_hash("not right")];
+        assert!(tree.verify_multi_proof(&wrong, &proof).is_err());
+
+        let missing = vec![tree.get_leaf_hash("b")];
+        assert!(tree.verify_multi_proof(&missing, &proof).is_err());
+
+        let leaves = vec![tree.get_leaf_hash("b"), tree.get_leaf_hash("e")];
+        let mut extra = proof.clone();
+        extra.siblings.push(tree.root_hash.clone());
+        assert!(tree.verify_multi_proof(&leaves, &extra).is_err());
+    }
+
+    #[test]
+    fn test_consistency_proof() {
+        let data = vec!["a", "b", "c", "d", "e", "f", "g", "h", "i"];
+        let tree = FullMerkleTree::create(data.as_slice(), Sha256Hasher {}).unwrap();
+        let roots: Vec<Hash> = (1..=data.len())
+            .map(|size| {
+                FullMerkleTree::create(&data[..size], Sha256Hasher {})
+                    .unwrap()
+                    .root_hash
+            })
+            .collect();
+
+        for new_size in 1..=data.len() {
+            for old_size in 1..=new_size {
+                let proof = tree.gen_consistency_proof(old_size, new_size).unwrap();
+                assert!(proof
+                    .verify(&roots[old_size - 1], &roots[new_size - 1], &tree.hasher)
+                    .is_ok());
+            }
+        }
+
+        assert!(tree.gen_consistency_proof(0, 3).is_err());
+        assert!(tree.gen_consistency_proof(4, 3).is_err());
+        assert!(tree.gen_consistency_proof(3, 10).is_err());
+    }
+
+    #[test]
+    fn test_consistency_proof_fails() {
+        let data = ["a", "b", "c", "d", "e", "f"];
+        let mut tree = FullMerkleTree::create(&data[..3], Sha256Hasher {}).unwrap();
+        let old_root = tree.root_hash.clone();
+        for el in &data[3..] {
+            tree.add_leaf(el);
+        }
+
+        let proof = tree.gen_consistency_proof(3, 6).unwrap();
+        assert!(proof
+            .verify(&old_root, &tree.root_hash, &tree.hasher)
+            .is_ok());
+
+        // history got rewritten
+        let rewritten = FullMerkleTree::create(&["a", "x", "c"], Sha256Hasher {}).unwrap();
+        assert!(proof
+            .verify(&rewritten.root_hash, &tree.root_hash, &tree.hasher)
+            .is_err());
+
+        let mut tampered = proof.clone();
+        tampered.path.pop();
+        assert!(tampered
+            .verify(&old_root, &tree.root_hash, &tree.hasher)
+            .is_err());
+    }
+
+    #[test]
+    fn test_plain_hashing_mode() {
+        let data = vec!["hello", "how", "are"];
+        let options = TreeOptions {
+            hashing_mode: HashingMode::Plain,
+            ..Default::default()
+        };
+        let tree =
+            FullMerkleTree::create_with_options(data.as_slice(), Sha256Hasher {}, options).unwrap();
+
+        let hasher = Sha256Hasher::new();
+        let expected_root_hash = hasher.get_combined_hash(
+            &hasher.get_combined_hash(
+                &hasher.get_hash_from_data(data[0]),
+                &hasher.get_hash_from_data(data[1]),
+            ),
+            &hasher.get_combined_hash(
+                &hasher.get_hash_from_data(data[2]),
+                &hasher.get_hash_from_data(data[2]),
+            ),
+        );
+        assert_eq!(tree.root_hash, expected_root_hash);
+        assert_eq!(tree.gen_proof(1).unwrap().options, options);
+    }
+
+    #[test]
+    fn test_interior_node_is_not_a_leaf() {
         let data = vec!["hello", "how", "are", "you"];
         let tree = FullMerkleTree::create(data.as_slice(), Sha256Hasher {}).unwrap();
-        let hash = &tree.hasher.get_hash_from_data("are");
-        let res = tree.get_leaf_by_hash(&hash);
-        assert_eq!(res.unwrap().borrow().value, *hash);
+
+        // present the children of the first interior node as the data of a leaf in a tree of two
+        let forged_data = [tree.get_leaf_hash("hello"), tree.get_leaf_hash("how")].concat();
+        let mut forged = tree.gen_proof(0).unwrap();
+        forged.path.remove(0);
+        forged.tree_size = 2;
+        assert!(tree
+            .verify_proof(&tree.get_leaf_hash(&forged_data), &forged)
+            .is_err());
+
+        // without domain separation the same forgery goes through
+        let options = TreeOptions {
+            hashing_mode: HashingMode::Plain,
+            ..Default::default()
+        };
+        let tree =
+            FullMerkleTree::create_with_options(data.as_slice(), Sha256Hasher {}, options).unwrap();
+        let forged_data = [tree.get_leaf_hash("hello"), tree.get_leaf_hash("how")].concat();
+        let mut forged = tree.gen_proof(0).unwrap();
+        forged.path.remove(0);
+        forged.tree_size = 2;
+        assert!(tree
+            .verify_proof(&tree.get_leaf_hash(&forged_data), &forged)
+            .is_ok());
+    }
+
+    const POLICIES: [OddNodePolicy; 3] = [
+        OddNodePolicy::Duplicate,
+        OddNodePolicy::Promote,
+        OddNodePolicy::PadToPowerOfTwo,
+    ];
+
+    fn with_policy(odd_node_policy: OddNodePolicy) -> TreeOptions {
+        TreeOptions {
+            odd_node_policy,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_odd_node_policies_root() {
+        let data = vec!["how", "are", "you"];
+        for policy in POLICIES {
+            let tree = FullMerkleTree::create_with_options(
+                data.as_slice(),
+                Sha256Hasher {},
+                with_policy(policy),
+            )
+            .unwrap();
+            let ab = tree.get_node_hash(&tree.get_leaf_hash(data[0]), &tree.get_leaf_hash(data[1]));
+            let c = tree.get_leaf_hash(data[2]);
+            let right = match policy {
+                OddNodePolicy::Duplicate => tree.get_node_hash(&c, &c),
+                OddNodePolicy::Promote => c,
+                OddNodePolicy::PadToPowerOfTwo => tree.get_node_hash(&c, &vec![0; 32]),
+            };
+            assert_eq!(tree.root_hash, tree.get_node_hash(&ab, &right));
+        }
+    }
+
+    #[test]
+    fn test_odd_node_policies_malleability() {
+        let short = ["a", "b", "c"];
+        let long = ["a", "b", "c", "c"];
+        for policy in POLICIES {
+            let options = with_policy(policy);
+            let short =
+                FullMerkleTree::create_with_options(&short, Sha256Hasher {}, options).unwrap();
+            let long =
+                FullMerkleTree::create_with_options(&long, Sha256Hasher {}, options).unwrap();
+            assert_eq!(
+                short.root_hash == long.root_hash,
+                policy == OddNodePolicy::Duplicate
+            );
+        }
+    }
+
+    #[test]
+    fn test_odd_node_policies_proofs() {
+        let data = vec!["a", "b", "c", "d", "e", "f", "g", "h", "i", "j", "k"];
+        for policy in POLICIES {
+            let options = with_policy(policy);
+            for size in 1..=data.len() {
+                let tree =
+                    FullMerkleTree::create_with_options(&data[..size], Sha256Hasher {}, options)
+                        .unwrap();
+                for (idx, el) in data[..size].iter().enumerate() {
+                    let proof = tree.gen_proof(idx).unwrap();
+                    assert!(tree.verify_proof(&tree.get_leaf_hash(el), &proof).is_ok());
+                }
+
+                let indices: Vec<usize> = (0..size).step_by(3).collect();
+                let leaf_hashes: Vec<Hash> = indices
+                    .iter()
+                    .map(|i| tree.get_leaf_hash(data[*i]))
+                    .collect();
+                let proof = tree.gen_multi_proof(&indices).unwrap();
+                assert!(tree.verify_multi_proof(&leaf_hashes, &proof).is_ok());
+
+                for old_size in 1..=size {
+                    let old = FullMerkleTree::create_with_options(
+                        &data[..old_size],
+                        Sha256Hasher {},
+                        options,
+                    )
+                    .unwrap();
+                    let proof = tree.gen_consistency_proof(old_size, size).unwrap();
+                    assert!(proof
+                        .verify(&old.root_hash, &tree.root_hash, &tree.hasher)
+                        .is_ok());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_branching_factor_root() {
+        let data = vec!["a", "b", "c", "d", "e"];
+        let [a, b, c, d, e] = data.clone().try_into().unwrap();
+        for policy in POLICIES {
+            let options = TreeOptions {
+                branching_factor: 3,
+                ..with_policy(policy)
+            };
+            let tree =
+                FullMerkleTree::create_with_options(data.as_slice(), Sha256Hasher {}, options)
+                    .unwrap();
+            let children_hash = |children: &[&Hash]| {
+                let children: Vec<Hash> = children.iter().map(|hash| hash.to_vec()).collect();
+                tree.hasher
+                    .get_children_hash(&children, HashingMode::DomainSeparated)
+            };
+            let [a, b, c, d, e] = [a, b, c, d, e].map(|el| tree.get_leaf_hash(el));
+            let abc = children_hash(&[&a, &b, &c]);
+            let zero = vec![0; 32];
+            let expected = match policy {
+                OddNodePolicy::Duplicate => {
+                    let dee = children_hash(&[&d, &e, &e]);
+                    children_hash(&[&abc, &dee, &dee])
+                }
+                OddNodePolicy::Promote => children_hash(&[&abc, &children_hash(&[&d, &e])]),
+                OddNodePolicy::PadToPowerOfTwo => {
+                    let zero_parent = children_hash(&[&zero, &zero, &zero]);
+                    children_hash(&[&abc, &children_hash(&[&d, &e, &zero]), &zero_parent])
+                }
+            };
+            assert_eq!(tree.root_hash, expected);
+        }
+    }
+
+    #[test]
+    fn test_branching_factor_proofs() {
+        let data: Vec<String> = (0..20).map(|i| i.to_string()).collect();
+        for branching_factor in [3, 4, 8] {
+            for policy in POLICIES {
+                let options = TreeOptions {
+                    branching_factor,
+                    ..with_policy(policy)
+                };
+                for size in 1..=data.len() {
+                    let tree = FullMerkleTree::create_with_options(
+                        &data[..size],
+                        Sha256Hasher {},
+                        options,
+                    )
+                    .unwrap();
+                    for (idx, el) in data[..size].iter().enumerate() {
+                        let proof = tree.gen_proof(idx).unwrap();
+                        assert!(tree.verify_proof(&tree.get_leaf_hash(el), &proof).is_ok());
+                    }
+
+                    let indices: Vec<usize> = (0..size).step_by(3).collect();
+                    let leaf_hashes: Vec<Hash> = indices
+                        .iter()
+                        .map(|i| tree.get_leaf_hash(&data[*i]))
+                        .collect();
+                    let proof = tree.gen_multi_proof(&indices).unwrap();
+                    assert!(tree.verify_multi_proof(&leaf_hashes, &proof).is_ok());
+
+                    for old_size in 1..=size {
+                        let old = FullMerkleTree::create_with_options(
+                            &data[..old_size],
+                            Sha256Hasher {},
+                            options,
+                        )
+                        .unwrap();
+                        let proof = tree.gen_consistency_proof(old_size, size).unwrap();
+                        assert!(proof
+                            .verify(&old.root_hash, &tree.root_hash, &tree.hasher)
+                            .is_ok());
+                    }
+                }
+            }
+        }
+
+        for branching_factor in [0, 1] {
+            let options = TreeOptions {
+                branching_factor,
+                ..Default::default()
+            };
+            assert_eq!(
+                FullMerkleTree::create_with_options(&data, Sha256Hasher {}, options).err(),
+                Some(MerkleError::InvalidBranchingFactor { branching_factor })
+            );
+        }
+    }
+
+    #[test]
+    fn test_incremental_updates_match_rebuild() {
+        for (policy, branching_factor) in POLICIES
+            .into_iter()
+            .flat_map(|policy| [2, 3, 4].map(|branching_factor| (policy, branching_factor)))
+        {
+            let options = TreeOptions {
+                branching_factor,
+                ..with_policy(policy)
+            };
+            let mut data: Vec<String> = vec!["a".to_string()];
+            let mut tree =
+                FullMerkleTree::create_with_options(&data, Sha256Hasher {}, options).unwrap();
+            // the links must be right too, not only the root
+            let check = |tree: &FullMerkleTree<Sha256Hasher>, data: &[String]| {
+                let expected =
+                    FullMerkleTree::create_with_options(data, Sha256Hasher {}, options).unwrap();
+                assert_eq!(tree.root_hash, expected.root_hash);
+                assert_eq!(tree.arena()[tree.root].value, expected.root_hash);
+                for idx in 0..data.len() {
+                    assert_eq!(tree.gen_proof(idx), expected.gen_proof(idx));
+                }
+            };
+
+            for i in 1..20 {
+                data.push(i.to_string());
+                tree.add_leaf(i.to_string());
+                check(&tree, &data);
+            }
+            for i in [0, 7, 18, 9, 16, 17] {
+                data[i] = format!("updated {i}");
+                tree.update_leaf(i, &data[i]).unwrap();
+                check(&tree, &data);
+            }
+            for i in 20..40 {
+                data.push(i.to_string());
+                tree.add_leaf(i.to_string());
+                data[i / 2] = format!("updated {i}");
+                tree.update_leaf(i / 2, &data[i / 2]).unwrap();
+                check(&tree, &data);
+            }
+            tree.delete_leaf(3).unwrap();
+            data.remove(3);
+            check(&tree, &data);
+        }
+    }
+
+    #[test]
+    fn test_large_trees_match() {
+        let data: Vec<String> = (0..3000).map(|i| i.to_string()).collect();
+        for (policy, branching_factor) in POLICIES
+            .into_iter()
+            .flat_map(|policy| [2, 3, 4].map(|branching_factor| (policy, branching_factor)))
+        {
+            let options = TreeOptions {
+                branching_factor,
+                ..with_policy(policy)
+            };
+            let full =
+                FullMerkleTree::create_with_options(&data, Sha256Hasher {}, options).unwrap();
+            let compact =
+                CompactMerkleTree::create_with_options(&data, Sha256Hasher {}, options).unwrap();
+            // appending hashes one node at a time
+            let mut incremental =
+                FullMerkleTree::create_with_options(&data[..1], Sha256Hasher {}, options).unwrap();
+            for el in &data[1..] {
+                incremental.add_leaf(el);
+            }
+
+            assert_eq!(full.root_hash, incremental.root_hash);
+            assert_eq!(compact.root_hash, incremental.root_hash);
+            assert_eq!(full.gen_proof(2999), incremental.gen_proof(2999));
+        }
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_create_parallel() {
+        // big enough for the levels to be split between threads
+        let data: Vec<String> = (0..3000).map(|i| i.to_string()).collect();
+        for (policy, branching_factor) in POLICIES
+            .into_iter()
+            .flat_map(|policy| [2, 3, 4].map(|branching_factor| (policy, branching_factor)))
+        {
+            let options = TreeOptions {
+                branching_factor,
+                ..with_policy(policy)
+            };
+            let full =
+                FullMerkleTree::create_with_options(&data, Sha256Hasher {}, options).unwrap();
+            let parallel =
+                FullMerkleTree::create_parallel_with_options(&data, Sha256Hasher {}, options)
+                    .unwrap();
+            assert_eq!(parallel.root_hash, full.root_hash);
+            assert_eq!(parallel.gen_proof(2999), full.gen_proof(2999));
+        }
+    }
+
+    #[test]
+    fn test_hasher_without_sync() {
+        // hashers don't have to be shared between threads, whatever the features
+        struct CountingHasher {
+            calls: std::cell::Cell<usize>,
+        }
+        impl Hasher for CountingHasher {
+            fn get_combined_hash(&self, a: &Hash, b: &Hash) -> Hash {
+                self.calls.set(self.calls.get() + 1);
+                Sha256Hasher {}.get_combined_hash(a, b)
+            }
+
+            fn get_hash_from_data<T: HashableData>(&self, el: T) -> Hash {
+                self.calls.set(self.calls.get() + 1);
+                Sha256Hasher {}.get_hash_from_data(el)
+            }
+        }
+
+        let data = vec!["hello", "how", "are", "you"];
+        let tree = FullMerkleTree::create(
+            &data,
+            CountingHasher {
+                calls: Default::default(),
+            },
+        )
+        .unwrap();
+        let expected = FullMerkleTree::create(&data, Sha256Hasher {}).unwrap();
+        assert_eq!(tree.root_hash, expected.root_hash);
+        assert!(tree.hasher.calls.get() > 0);
+    }
+
+    #[test]
+    fn test_arena_links() {
+        for policy in POLICIES {
+            let mut tree =
+                FullMerkleTree::create_with_options(&["0"], Sha256Hasher {}, with_policy(policy))
+                    .unwrap();
+            for i in 1..300 {
+                tree.add_leaf(i.to_string());
+
+                // every leaf reaches the root, which has no parent
+                let arena = tree.arena();
+                for leaf in &tree.leaves {
+                    assert_eq!(arena.get_path(*leaf).last(), Some(tree.root));
+                }
+                // the nodes replaced by the additions don't pile up
+                assert!(arena.len() <= 8 * tree.leaves.len());
+            }
+        }
+    }
+
+    #[test]
+    fn test_leaf_index() {
+        let data = vec!["a", "b", "a", "c", "a"];
+        let mut indexed = FullMerkleTree::create(data.as_slice(), Sha256Hasher {}).unwrap();
+        indexed.enable_leaf_index();
+        let mut scanned = FullMerkleTree::create(data.as_slice(), Sha256Hasher {}).unwrap();
+        let [a, b, c, d] = ["a", "b", "c", "d"].map(|el| indexed.get_leaf_hash(el));
+
+        assert_eq!(indexed.get_leaf_indices(&a), vec![0, 2, 4]);
+        assert_eq!(indexed.contains_hash(&b).unwrap().0, 1);
+        assert!(indexed.get_leaf_by_hash(&d).is_none());
+
+        indexed.add_leaf("b");
+        scanned.add_leaf("b");
+        indexed.update_leaf(2, "d").unwrap();
+        scanned.update_leaf(2, "d").unwrap();
+        indexed.delete_leaf(1).unwrap();
+        scanned.delete_leaf(1).unwrap();
+        for hash in [&a, &b, &c, &d] {
+            assert_eq!(
+                indexed.get_leaf_indices(hash),
+                scanned.get_leaf_indices(hash)
+            );
+        }
+        assert_eq!(indexed.get_leaf_indices(&a), vec![0, 3]);
+        assert_eq!(indexed.get_leaf_indices(&d), vec![1]);
+        assert_eq!(indexed.contains_hash(&b).unwrap().0, 4);
+    }
+
+    #[test]
+    fn test_consistency_proof_of_prefix() {
+        let data: Vec<String> = (0..20).map(|i| i.to_string()).collect();
+        for (policy, branching_factor) in POLICIES
+            .into_iter()
+            .flat_map(|policy| [2, 3, 4].map(|branching_factor| (policy, branching_factor)))
+        {
+            let options = TreeOptions {
+                branching_factor,
+                ..with_policy(policy)
+            };
+            // grown leaf by leaf, so the nodes of the right edge were rewritten along the way
+            let mut tree =
+                FullMerkleTree::create_with_options(&data[..1], Sha256Hasher {}, options).unwrap();
+            for el in &data[1..] {
+                tree.add_leaf(el);
+            }
+
+            for new_size in 1..=data.len() {
+                let prefix = FullMerkleTree::create_with_options(
+                    &data[..new_size],
+                    Sha256Hasher {},
+                    options,
+                )
+                .unwrap();
+                for old_size in 1..=new_size {
+                    assert_eq!(
+                        tree.gen_consistency_proof(old_size, new_size),
+                        prefix.gen_consistency_proof(old_size, new_size)
+                    );
+                }
+            }
+        }
     }
 }
@@ -0,0 +1,138 @@
+use merkle_tree::mk::mmr::MerkleMountainRange;
+use merkle_tree::utils::crypto::*;
+
+#[cfg(test)]
+mod tests {
+    use merkle_tree::error::MerkleError;
+
+    use super::*;
+
+    fn create(size: usize) -> MerkleMountainRange<Sha256Hasher> {
+        let mut mmr = MerkleMountainRange::new(Sha256Hasher {});
+        for i in 0..size {
+            assert_eq!(mmr.append(i.to_string()), i);
+        }
+        mmr
+    }
+
+    #[test]
+    fn test_root() {
+        let mmr = create(3);
+        let [a, b, c] = ["0", "1", "2"].map(|el| mmr.get_leaf_hash(el));
+        let mode = HashingMode::DomainSeparated;
+
+        let ab = mmr.hasher.get_node_hash(&a, &b, mode);
+        assert_eq!(mmr.get_peaks_at(3).unwrap(), vec![ab.clone(), c.clone()]);
+        assert_eq!(
+            mmr.root_hash().unwrap(),
+            mmr.hasher.get_node_hash(&ab, &c, mode)
+        );
+        // a single mountain is its own root
+        assert_eq!(mmr.get_root_at(2).unwrap(), ab);
+        assert_eq!(mmr.get_root_at(1).unwrap(), a);
+        assert!(MerkleMountainRange::new(Sha256Hasher {})
+            .root_hash()
+            .is_none());
+    }
+
+    #[test]
+    fn test_former_roots_are_kept() {
+        let mmr = create(20);
+        for size in 1..=20 {
+            assert_eq!(mmr.get_root_at(size), Ok(create(size).root_hash().unwrap()));
+        }
+        assert_eq!(
+            mmr.get_root_at(21),
+            Err(MerkleError::IndexOutOfRange { index: 21, len: 20 })
+        );
+    }
+
+    #[test]
+    fn test_proofs() {
+        let mmr = create(20);
+        for size in 1..=20 {
+            let root = mmr.get_root_at(size).unwrap();
+            for leaf_idx in 0..size {
+                let leaf_hash = mmr.get_leaf_hash(leaf_idx.to_string());
+                let proof = mmr.gen_proof_at(leaf_idx, size).unwrap();
+                assert!(proof.verify(&root, &leaf_hash, &mmr.hasher).is_ok());
+                assert_eq!(
+                    proof.verify(&root, &mmr.get_leaf_hash("wrong"), &mmr.hasher),
+                    Err(MerkleError::RootMismatch)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_proofs_stay_verifiable() {
+        let mut mmr = create(5);
+        let root = mmr.root_hash().unwrap();
+        let proof = mmr.gen_proof(2).unwrap();
+
+        for i in 5..30 {
+            mmr.append(i.to_string());
+        }
+        let leaf_hash = mmr.get_leaf_hash("2");
+        assert!(proof.verify(&root, &leaf_hash, &mmr.hasher).is_ok());
+        assert_eq!(mmr.gen_proof_at(2, 5).unwrap(), proof);
+        assert!(mmr
+            .gen_proof(2)
+            .unwrap()
+            .verify(&mmr.root_hash().unwrap(), &leaf_hash, &mmr.hasher)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_proof_errors() {
+        let mmr = create(7);
+        let root = mmr.root_hash().unwrap();
+        let leaf_hash = mmr.get_leaf_hash("1");
+        let proof = mmr.gen_proof(1).unwrap();
+        assert_eq!(proof.path.len(), 2);
+        assert_eq!(proof.peaks.len(), 2);
+
+        assert_eq!(
+            mmr.gen_proof(7),
+            Err(MerkleError::IndexOutOfRange { index: 7, len: 7 })
+        );
+        let mut short = proof.clone();
+        short.path.pop();
+        assert_eq!(
+            short.verify(&root, &leaf_hash, &mmr.hasher),
+            Err(MerkleError::ProofLengthMismatch {
+                expected: 2,
+                actual: 1
+            })
+        );
+        let mut grown = proof.clone();
+        grown.size = 8;
+        assert!(grown.verify(&root, &leaf_hash, &mmr.hasher).is_err());
+        assert!(proof
+            .verify(&root, &leaf_hash, &Blake2s256Hasher::new())
+            .is_err());
+    }
+
+    #[test]
+    fn test_sorted_pair_rejected() {
+        let mode = HashingMode::SortedPair;
+        let err = MerkleError::UnsupportedHashingMode { hashing_mode: mode };
+        assert_eq!(
+            MerkleMountainRange::new_with_hashing_mode(Sha256Hasher {}, mode).err(),
+            Some(err.clone())
+        );
+
+        // sorted pairs would let the proof pass for any leaf of its mountain
+        let mmr = create(4);
+        let mut proof = mmr.gen_proof(1).unwrap();
+        proof.hashing_mode = mode;
+        assert_eq!(
+            proof.verify(
+                &mmr.root_hash().unwrap(),
+                &mmr.get_leaf_hash("1"),
+                &mmr.hasher
+            ),
+            Err(err)
+        );
+    }
+}
@@ -0,0 +1,234 @@
+use merkle_tree::mk::rfc6962::Rfc6962MerkleTree;
+use merkle_tree::utils::crypto::*;
+
+#[cfg(test)]
+mod tests {
+    use merkle_tree::error::MerkleError;
+    use merkle_tree::mk::compact::CompactMerkleTree;
+    use merkle_tree::mk::options::TreeOptions;
+
+    use super::*;
+
+    fn from_hex(hex: &str) -> Hash {
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    /// The leaves of the RFC 6962 test vectors, as used by the Certificate Transparency
+    /// implementations.
+    const LEAVES: [&str; 8] = [
+        "",
+        "00",
+        "10",
+        "2021",
+        "3031",
+        "40414243",
+        "5051525354555657",
+        "606162636465666768696a6b6c6d6e6f",
+    ];
+
+    /// The roots of the trees made of the first 1 to 8 leaves.
+    const ROOTS: [&str; 8] = [
+        "6e340b9cffb37a989ca544e6bb780a2c78901d3fb33738768511a30617afa01d",
+        "fac54203e7cc696cf0dfcb42c92a1d9dbaf70ad9e621f4bd8d98662f00e3c125",
+        "aeb6bcfe274b70a14fb067a5e5578264db0fa9b51af5e0ba159158f329e06e77",
+        "d37ee418976dd95753c1c73862b9398fa2a2cf9b4ff0fdfe8b30cd95209614b7",
+        "4e3bbb1f7b478dcfe71fb631631519a3bca12c9aefca1612bfce4c13a86264d4",
+        "76e67dadbcdf1e10e1b74ddc608abd2f98dfb16fbce75277b5232a127f2087ef",
+        "ddb89be403809e325750d3d263cd78929c2942b7942a34b77e122c9594a74c8c",
+        "5dc9da79a70659a9ad559cb701ded9a2ab9d823aad2f4960cfe370eff4604328",
+    ];
+
+    /// Audit paths as (leaf index, tree size, path).
+    const AUDIT_PATHS: [(usize, usize, &[&str]); 5] = [
+        (0, 1, &[]),
+        (
+            0,
+            8,
+            &[
+                "96a296d224f285c67bee93c30f8a309157f0daa35dc5b87e410b78630a09cfc7",
+                "5f083f0a1a33ca076a95279832580db3e0ef4584bdff1f54c8a360f50de3031e",
+                "6b47aaf29ee3c2af9af889bc1fb9254dabd31177f16232dd6aab035ca39bf6e4",
+            ],
+        ),
+        (
+            5,
+            8,
+            &[
+                "bc1a0643b12e4d2d7c77918f44e0f4f79a838b6cf9ec5b5c283e1f4d88599e6b",
+                "ca854ea128ed050b41b35ffc1b87b8eb2bde461e9e3b5596ece6b9d5975a0ae0",
+                "d37ee418976dd95753c1c73862b9398fa2a2cf9b4ff0fdfe8b30cd95209614b7",
+            ],
+        ),
+        (
+            2,
+            3,
+            &["fac54203e7cc696cf0dfcb42c92a1d9dbaf70ad9e621f4bd8d98662f00e3c125"],
+        ),
+        (
+            1,
+            5,
+            &[
+                "6e340b9cffb37a989ca544e6bb780a2c78901d3fb33738768511a30617afa01d",
+                "5f083f0a1a33ca076a95279832580db3e0ef4584bdff1f54c8a360f50de3031e",
+                "bc1a0643b12e4d2d7c77918f44e0f4f79a838b6cf9ec5b5c283e1f4d88599e6b",
+            ],
+        ),
+    ];
+
+    /// Consistency proofs as (old size, new size, path).
+    const CONSISTENCY_PATHS: [(usize, usize, &[&str]); 4] = [
+        (1, 1, &[]),
+        (
+            1,
+            8,
+            &[
+                "96a296d224f285c67bee93c30f8a309157f0daa35dc5b87e410b78630a09cfc7",
+                "5f083f0a1a33ca076a95279832580db3e0ef4584bdff1f54c8a360f50de3031e",
+                "6b47aaf29ee3c2af9af889bc1fb9254dabd31177f16232dd6aab035ca39bf6e4",
+            ],
+        ),
+        (
+            6,
+            8,
+            &[
+                "0ebc5d3437fbe2db158b9f126a1d118e308181031d0a949f8dededebc558ef6a",
+                "ca854ea128ed050b41b35ffc1b87b8eb2bde461e9e3b5596ece6b9d5975a0ae0",
+                "d37ee418976dd95753c1c73862b9398fa2a2cf9b4ff0fdfe8b30cd95209614b7",
+            ],
+        ),
+        (
+            2,
+            5,
+            &[
+                "5f083f0a1a33ca076a95279832580db3e0ef4584bdff1f54c8a360f50de3031e",
+                "bc1a0643b12e4d2d7c77918f44e0f4f79a838b6cf9ec5b5c283e1f4d88599e6b",
+            ],
+        ),
+    ];
+
+    fn create(size: usize) -> Rfc6962MerkleTree<Sha256Hasher> {
+        let mut tree = Rfc6962MerkleTree::new(Sha256Hasher {});
+        for leaf in &LEAVES[..size] {
+            tree.append(from_hex(leaf));
+        }
+        tree
+    }
+
+    #[test]
+    fn test_roots() {
+        let tree = create(8);
+        assert_eq!(
+            Rfc6962MerkleTree::new(Sha256Hasher {}).root_hash(),
+            from_hex("e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855")
+        );
+        for (size, root) in (1..=8).zip(ROOTS) {
+            assert_eq!(tree.get_root_at(size), Ok(from_hex(root)));
+            assert_eq!(create(size).root_hash(), from_hex(root));
+        }
+        assert_eq!(
+            tree.get_root_at(9),
+            Err(MerkleError::IndexOutOfRange { index: 9, len: 8 })
+        );
+    }
+
+    #[test]
+    fn test_audit_paths() {
+        let tree = create(8);
+        for (leaf_idx, size, path) in AUDIT_PATHS {
+            let path: Vec<Hash> = path.iter().map(|hash| from_hex(hash)).collect();
+            let proof = tree.gen_audit_path_at(leaf_idx, size).unwrap();
+            assert_eq!(proof.path, path);
+
+            let root = from_hex(ROOTS[size - 1]);
+            let leaf_hash = tree.get_leaf_hash(from_hex(LEAVES[leaf_idx]));
+            assert!(proof.verify(&root, &leaf_hash, &tree.hasher).is_ok());
+        }
+
+        for size in 1..=8 {
+            for (leaf_idx, leaf) in LEAVES[..size].iter().enumerate() {
+                let root = from_hex(ROOTS[size - 1]);
+                let leaf_hash = tree.get_leaf_hash(from_hex(leaf));
+                let proof = tree.gen_audit_path_at(leaf_idx, size).unwrap();
+                assert!(proof.verify(&root, &leaf_hash, &tree.hasher).is_ok());
+
+                // the leaf can't be claimed at another position
+                let mut moved = proof.clone();
+                moved.leaf_idx = (leaf_idx + 1) % size;
+                assert!(size == 1 || moved.verify(&root, &leaf_hash, &tree.hasher).is_err());
+            }
+        }
+    }
+
+    #[test]
+    fn test_consistency_paths() {
+        let tree = create(8);
+        for (old_size, new_size, path) in CONSISTENCY_PATHS {
+            let path: Vec<Hash> = path.iter().map(|hash| from_hex(hash)).collect();
+            let proof = tree.gen_consistency_path(old_size, new_size).unwrap();
+            assert_eq!(proof.path, path);
+        }
+
+        for new_size in 1..=8 {
+            for old_size in 1..=new_size {
+                let old_root = from_hex(ROOTS[old_size - 1]);
+                let new_root = from_hex(ROOTS[new_size - 1]);
+                let proof = tree.gen_consistency_path(old_size, new_size).unwrap();
+                assert!(proof.verify(&old_root, &new_root, &tree.hasher).is_ok());
+                assert_eq!(
+                    proof.verify(&new_root, &old_root, &tree.hasher).is_ok(),
+                    old_root == new_root
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_malformed_proofs() {
+        let tree = create(8);
+        let leaf_hash = tree.get_leaf_hash(from_hex(LEAVES[0]));
+        let mut proof = tree.gen_audit_path(0).unwrap();
+        proof.path.pop();
+        assert_eq!(
+            proof.verify(&tree.root_hash(), &leaf_hash, &tree.hasher),
+            Err(MerkleError::ProofLengthMismatch {
+                expected: 3,
+                actual: 2
+            })
+        );
+
+        let mut proof = tree.gen_consistency_path(3, 8).unwrap();
+        proof.path.push(tree.root_hash());
+        assert_eq!(
+            proof.verify(&from_hex(ROOTS[2]), &tree.root_hash(), &tree.hasher),
+            Err(MerkleError::ProofLengthMismatch {
+                expected: 4,
+                actual: 5
+            })
+        );
+        assert_eq!(
+            tree.gen_consistency_path(0, 8).err(),
+            Some(MerkleError::InvalidSizes {
+                old_size: 0,
+                new_size: 8,
+                len: 8
+            })
+        );
+    }
+
+    #[test]
+    fn test_same_root_as_compact_tree() {
+        for size in 1..=8 {
+            let leaves: Vec<Hash> = LEAVES[..size].iter().map(|leaf| from_hex(leaf)).collect();
+            let compact = CompactMerkleTree::create_with_options(
+                &leaves,
+                Sha256Hasher {},
+                TreeOptions::rfc6962(),
+            )
+            .unwrap();
+            assert_eq!(compact.root_hash, from_hex(ROOTS[size - 1]));
+        }
+    }
+}
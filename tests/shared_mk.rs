@@ -0,0 +1,129 @@
+use merkle_tree::mk::shared::SharedMerkleTree;
+use merkle_tree::utils::crypto::*;
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+    use std::thread;
+
+    use merkle_tree::mk::compact::CompactMerkleTree;
+    use merkle_tree::mk::frontier::FrontierMerkleTree;
+    use merkle_tree::mk::full::FullMerkleTree;
+    use merkle_tree::mk::mk::MerkleTree;
+    use merkle_tree::mk::mmr::MerkleMountainRange;
+    use merkle_tree::mk::rfc6962::Rfc6962MerkleTree;
+    use merkle_tree::mk::sorted::SortedMerkleTree;
+    use merkle_tree::mk::sparse::SparseMerkleTree;
+    use merkle_tree::mk::standard::StandardMerkleTree;
+    use merkle_tree::mk::sum::MerkleSumTree;
+
+    use super::*;
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn test_trees_are_send_and_sync() {
+        assert_send_sync::<FullMerkleTree<Sha256Hasher>>();
+        assert_send_sync::<CompactMerkleTree<Sha256Hasher>>();
+        assert_send_sync::<SparseMerkleTree<Sha256Hasher>>();
+        assert_send_sync::<MerkleMountainRange<Sha256Hasher>>();
+        assert_send_sync::<FrontierMerkleTree<Sha256Hasher>>();
+        assert_send_sync::<MerkleSumTree<Sha256Hasher>>();
+        assert_send_sync::<SortedMerkleTree<Keccak256Hasher>>();
+        assert_send_sync::<StandardMerkleTree<Keccak256Hasher>>();
+        assert_send_sync::<Rfc6962MerkleTree<DoubleSha256Hasher>>();
+        assert_send_sync::<SharedMerkleTree<FullMerkleTree<Blake2s256Hasher>>>();
+    }
+
+    #[test]
+    fn test_readers_and_writer() {
+        let tree = FullMerkleTree::create(&["0"], Sha256Hasher {}).unwrap();
+        let shared = SharedMerkleTree::new(tree);
+
+        thread::scope(|scope| {
+            let writer = shared.clone();
+            scope.spawn(move || {
+                for i in 1..200 {
+                    writer.write(|tree| tree.add_leaf(i.to_string()));
+                }
+            });
+
+            for _ in 0..4 {
+                let reader = shared.clone();
+                scope.spawn(move || {
+                    for _ in 0..200 {
+                        let tree = reader.read();
+                        let idx = tree.leaves.len() - 1;
+                        let proof = tree.gen_proof(idx).unwrap();
+                        let leaf_hash = tree.get_leaf_hash(idx.to_string());
+                        assert!(tree.verify_proof(&leaf_hash, &proof).is_ok());
+                    }
+                });
+            }
+        });
+
+        let tree = shared.read();
+        let expected: Vec<String> = (0..200).map(|i| i.to_string()).collect();
+        assert_eq!(
+            tree.root_hash,
+            FullMerkleTree::create(&expected, Sha256Hasher {})
+                .unwrap()
+                .root_hash
+        );
+    }
+
+    #[test]
+    fn test_reader_completes_during_write() {
+        let tree = FullMerkleTree::create(&["0", "1"], Sha256Hasher {}).unwrap();
+        let shared = SharedMerkleTree::new(tree);
+        let (updated_tx, updated_rx) = mpsc::channel();
+        let (done_tx, done_rx) = mpsc::channel();
+
+        thread::scope(|scope| {
+            let writer = shared.clone();
+            scope.spawn(move || {
+                writer.write(|tree| {
+                    // deleting rebuilds the whole tree, and the write isn't over until the
+                    // reader is done
+                    tree.delete_leaf(0).unwrap();
+                    tree.add_leaf("2");
+                    updated_tx.send(()).unwrap();
+                    done_rx.recv().unwrap();
+                })
+            });
+
+            updated_rx.recv().unwrap();
+            let tree = shared.read();
+            assert_eq!(tree.leaves.len(), 2);
+            let proof = tree.gen_proof(0).unwrap();
+            assert!(tree.verify_proof(&tree.get_leaf_hash("0"), &proof).is_ok());
+            done_tx.send(()).unwrap();
+        });
+
+        // the version read before is kept by whoever still holds it
+        let tree = shared.read();
+        let expected = FullMerkleTree::create(&["1", "2"], Sha256Hasher {}).unwrap();
+        assert_eq!(tree.root_hash, expected.root_hash);
+    }
+
+    #[test]
+    fn test_panicking_write_publishes_nothing() {
+        let tree = CompactMerkleTree::create(&["0"], Sha256Hasher {}).unwrap();
+        let shared = SharedMerkleTree::new(tree);
+        let root_hash = shared.read().root_hash.clone();
+
+        let writer = shared.clone();
+        let result = thread::spawn(move || {
+            writer.write(|tree| {
+                tree.add_leaf("1");
+                panic!("the write fails halfway");
+            })
+        })
+        .join();
+        assert!(result.is_err());
+
+        assert_eq!(shared.read().root_hash, root_hash);
+        shared.write(|tree| tree.add_leaf("1"));
+        assert_eq!(shared.read().leaves.len(), 2);
+    }
+}
@@ -0,0 +1,175 @@
+use merkle_tree::mk::snapshot::TreeSnapshot;
+use merkle_tree::utils::crypto::*;
+
+#[cfg(test)]
+mod tests {
+    use merkle_tree::error::MerkleError;
+    use merkle_tree::mk::compact::CompactMerkleTree;
+    use merkle_tree::mk::full::FullMerkleTree;
+    use merkle_tree::mk::mk::MerkleTree;
+    use merkle_tree::mk::options::{OddNodePolicy, TreeOptions};
+
+    use super::*;
+
+    const POLICIES: [OddNodePolicy; 3] = [
+        OddNodePolicy::Duplicate,
+        OddNodePolicy::Promote,
+        OddNodePolicy::PadToPowerOfTwo,
+    ];
+
+    fn get_data(size: usize) -> Vec<String> {
+        (0..size).map(|i| i.to_string()).collect()
+    }
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        for (policy, branching_factor) in POLICIES
+            .into_iter()
+            .flat_map(|policy| [2, 3].map(|branching_factor| (policy, branching_factor)))
+        {
+            let options = TreeOptions {
+                odd_node_policy: policy,
+                branching_factor,
+                ..Default::default()
+            };
+            for size in [1, 2, 5, 10] {
+                let data = get_data(size);
+                let compact =
+                    CompactMerkleTree::create_with_options(&data, Sha256Hasher {}, options)
+                        .unwrap();
+                let full =
+                    FullMerkleTree::create_with_options(&data, Sha256Hasher {}, options).unwrap();
+                let snapshot = compact.snapshot();
+                // both trees save the same levels
+                assert_eq!(full.snapshot(), snapshot);
+                assert_eq!(
+                    snapshot.levels.last().unwrap_or(&snapshot.leaves),
+                    &vec![snapshot.root_hash.clone()]
+                );
+
+                let loaded =
+                    CompactMerkleTree::from_snapshot(snapshot.clone(), Sha256Hasher {}).unwrap();
+                assert_eq!(loaded.root_hash, compact.root_hash);
+                assert_eq!(loaded.gen_proof(size - 1), compact.gen_proof(size - 1));
+                let loaded = FullMerkleTree::from_snapshot(snapshot, Sha256Hasher {}).unwrap();
+                assert_eq!(loaded.root_hash, full.root_hash);
+                assert_eq!(loaded.gen_proof(size - 1), full.gen_proof(size - 1));
+            }
+        }
+    }
+
+    #[test]
+    fn test_snapshot_after_updates() {
+        for policy in POLICIES {
+            let options = TreeOptions {
+                odd_node_policy: policy,
+                ..Default::default()
+            };
+            let mut data = get_data(1);
+            let mut tree =
+                FullMerkleTree::create_with_options(&data, Sha256Hasher {}, options).unwrap();
+            for i in 1..12 {
+                data.push(i.to_string());
+                tree.add_leaf(i.to_string());
+                tree.update_leaf(i / 2, "updated").unwrap();
+                data[i / 2] = "updated".to_string();
+
+                let expected =
+                    CompactMerkleTree::create_with_options(&data, Sha256Hasher {}, options)
+                        .unwrap();
+                assert_eq!(tree.snapshot(), expected.snapshot());
+            }
+        }
+    }
+
+    #[test]
+    fn test_tampered_snapshot() {
+        let tree = CompactMerkleTree::create(&get_data(5), Sha256Hasher {}).unwrap();
+        let load = |snapshot: TreeSnapshot| {
+            let compact = CompactMerkleTree::from_snapshot(snapshot.clone(), Sha256Hasher {});
+            let full = FullMerkleTree::from_snapshot(snapshot, Sha256Hasher {});
+            assert_eq!(compact.as_ref().err(), full.as_ref().err());
+            compact.err()
+        };
+
+        let mut snapshot = tree.snapshot();
+        snapshot.leaves[3][0] ^= 1;
+        assert_eq!(load(snapshot), Some(MerkleError::CorruptedSnapshot));
+
+        let mut snapshot = tree.snapshot();
+        snapshot.levels[1][1][0] ^= 1;
+        assert_eq!(load(snapshot), Some(MerkleError::CorruptedSnapshot));
+
+        let mut snapshot = tree.snapshot();
+        snapshot.levels.pop();
+        assert_eq!(load(snapshot), Some(MerkleError::CorruptedSnapshot));
+
+        let mut snapshot = tree.snapshot();
+        snapshot.root_hash[0] ^= 1;
+        assert_eq!(load(snapshot), Some(MerkleError::CorruptedSnapshot));
+
+        let mut snapshot = tree.snapshot();
+        snapshot.options.odd_node_policy = OddNodePolicy::Promote;
+        assert_eq!(load(snapshot), Some(MerkleError::CorruptedSnapshot));
+
+        let mut snapshot = tree.snapshot();
+        snapshot.options.branching_factor = 1;
+        assert_eq!(
+            load(snapshot),
+            Some(MerkleError::InvalidBranchingFactor {
+                branching_factor: 1
+            })
+        );
+
+        let mut snapshot = tree.snapshot();
+        snapshot.leaves.clear();
+        assert_eq!(load(snapshot), Some(MerkleError::EmptyInput));
+    }
+
+    #[test]
+    fn test_snapshot_with_another_hasher() {
+        let tree = FullMerkleTree::create(&get_data(4), Sha256Hasher {}).unwrap();
+        let err = FullMerkleTree::from_snapshot(tree.snapshot(), Blake2s256Hasher {}).err();
+
+        assert_eq!(
+            err,
+            Some(MerkleError::AlgorithmMismatch {
+                expected: HashAlgorithm::Blake2s256,
+                actual: HashAlgorithm::Sha256,
+            })
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        let data = get_data(7);
+        let compact = CompactMerkleTree::create(&data, Sha256Hasher {}).unwrap();
+        let full = FullMerkleTree::create(&data, Sha256Hasher {}).unwrap();
+
+        let json = serde_json::to_string(&compact).unwrap();
+        assert_eq!(json, serde_json::to_string(&full).unwrap());
+        let loaded: FullMerkleTree<Sha256Hasher> = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.root_hash, compact.root_hash);
+        let loaded: CompactMerkleTree<Sha256Hasher> = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.gen_proof(6), compact.gen_proof(6));
+
+        // a snapshot of the same leaves with another hasher
+        assert!(serde_json::from_str::<CompactMerkleTree<Keccak256Hasher>>(&json).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_rejects_tampered_root() {
+        let tree = CompactMerkleTree::create(&get_data(7), Sha256Hasher {}).unwrap();
+        let mut snapshot = tree.snapshot();
+        snapshot.root_hash = vec![0; 32];
+        let json = serde_json::to_string(&snapshot).unwrap();
+
+        let err = serde_json::from_str::<CompactMerkleTree<Sha256Hasher>>(&json).err();
+        assert!(err
+            .unwrap()
+            .to_string()
+            .contains("snapshot hashes don't match"));
+    }
+}
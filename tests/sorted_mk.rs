@@ -0,0 +1,251 @@
+use merkle_tree::mk::options::TreeOptions;
+use merkle_tree::mk::proof::MerkleProof;
+use merkle_tree::mk::sorted::{Neighbor, NonMembershipProof, SortedMerkleTree};
+use merkle_tree::utils::crypto::*;
+
+#[cfg(test)]
+mod tests {
+    use merkle_tree::error::MerkleError;
+    use merkle_tree::mk::compact::CompactMerkleTree;
+    use merkle_tree::mk::mk::MerkleTree;
+
+    use super::*;
+
+    fn create(size: usize) -> SortedMerkleTree<Sha256Hasher> {
+        let data: Vec<String> = (0..size).map(|i| i.to_string()).collect();
+        SortedMerkleTree::create(&data, Sha256Hasher {}).unwrap()
+    }
+
+    #[test]
+    fn test_leaves_are_sorted() {
+        let data = ["c", "a", "b", "a"];
+        let tree = SortedMerkleTree::create(&data, Sha256Hasher {}).unwrap();
+        let leaves: Vec<&Hash> = tree.tree().leaves.iter().map(|leaf| &leaf.value).collect();
+
+        assert_eq!(leaves.len(), 3);
+        assert!(leaves.windows(2).all(|pair| pair[0] < pair[1]));
+        // the order of the data doesn't change the root
+        let shuffled = SortedMerkleTree::create(&["b", "a", "c"], Sha256Hasher {}).unwrap();
+        assert_eq!(tree.root_hash(), shuffled.root_hash());
+    }
+
+    #[test]
+    fn test_membership_proofs() {
+        let tree = create(10);
+        for i in 0..10 {
+            let el = i.to_string();
+            assert!(tree.contains(&el));
+            assert!(tree.gen_non_membership_proof(&el).unwrap().is_none());
+            let proof = tree.gen_proof(&el).unwrap();
+            assert!(proof
+                .verify(
+                    tree.root_hash(),
+                    &tree.get_leaf_hash(&el),
+                    &tree.tree().hasher
+                )
+                .is_ok());
+        }
+        assert!(!tree.contains("10"));
+        assert!(tree.gen_proof("10").is_none());
+    }
+
+    #[test]
+    fn test_non_membership_proofs() {
+        for size in 1..12 {
+            let tree = create(size);
+            for i in size..size + 30 {
+                let leaf_hash = tree.get_leaf_hash(i.to_string());
+                let proof = tree
+                    .gen_non_membership_proof(i.to_string())
+                    .unwrap()
+                    .unwrap();
+                assert!(proof
+                    .verify(tree.root_hash(), size, &leaf_hash, &tree.tree().hasher)
+                    .is_ok());
+            }
+        }
+    }
+
+    #[test]
+    fn test_non_membership_proof_fails() {
+        let tree = create(8);
+        let hasher = Sha256Hasher {};
+        let absent = tree.get_leaf_hash("absent");
+        let proof = tree.gen_non_membership_proof("absent").unwrap().unwrap();
+        assert!(proof.verify(tree.root_hash(), 8, &absent, &hasher).is_ok());
+        // "absent" falls in the middle of the tree
+        assert!(proof.left.as_ref().unwrap().proof.leaf_idx > 0 && proof.right.is_some());
+
+        // a value in the tree is never between its neighbors
+        for i in 0..8 {
+            let present = tree.get_leaf_hash(i.to_string());
+            assert!(proof
+                .verify(tree.root_hash(), 8, &present, &hasher)
+                .is_err());
+        }
+
+        // dropping a neighbor only works at the edges of the tree
+        let mut one_sided = proof.clone();
+        one_sided.right = None;
+        assert_eq!(
+            one_sided.verify(tree.root_hash(), 8, &absent, &hasher),
+            Err(MerkleError::NotBracketed)
+        );
+
+        // neighbors must be next to each other
+        let before_left = proof.left.as_ref().unwrap().proof.leaf_idx - 1;
+        let data = (0..8)
+            .map(|i| i.to_string())
+            .find(|el| tree.get_leaf_hash(el) == tree.tree().leaves[before_left].value)
+            .unwrap();
+        let mut gapped = proof.clone();
+        gapped.left = Some(Neighbor {
+            data: data.into_bytes(),
+            proof: tree.tree().gen_proof(before_left).unwrap(),
+        });
+        assert_eq!(
+            gapped.verify(tree.root_hash(), 8, &absent, &hasher),
+            Err(MerkleError::NotBracketed)
+        );
+
+        // a tree that isn't sorted gives no proof, so another root doesn't verify
+        let unsorted = CompactMerkleTree::create(&["0", "1", "2"], Sha256Hasher {}).unwrap();
+        assert_eq!(
+            proof.verify(&unsorted.root_hash, 8, &absent, &hasher),
+            Err(MerkleError::RootMismatch)
+        );
+    }
+
+    /// A proof with `node` on the side of `leaf_hash` it would bracket in a sorted tree.
+    fn bracket(node: Neighbor, node_hash: &Hash, leaf_hash: &Hash) -> NonMembershipProof {
+        match node_hash < leaf_hash {
+            true => NonMembershipProof {
+                left: Some(node),
+                right: None,
+            },
+            false => NonMembershipProof {
+                left: None,
+                right: Some(node),
+            },
+        }
+    }
+
+    #[test]
+    fn test_root_is_not_a_neighbor() {
+        let tree =
+            SortedMerkleTree::create(&["alice", "bob", "carol", "dave"], Sha256Hasher {}).unwrap();
+        let hasher = Sha256Hasher {};
+        let root = tree.root_hash().clone();
+        let bob = tree.get_leaf_hash("bob");
+
+        // the root as the only leaf of a tree of one
+        let forged = bracket(
+            Neighbor {
+                data: root.clone(),
+                proof: MerkleProof::new(
+                    0,
+                    1,
+                    HashAlgorithm::Sha256,
+                    TreeOptions::default(),
+                    vec![],
+                ),
+            },
+            &root,
+            &bob,
+        );
+        assert_eq!(
+            forged.verify(&root, tree.len(), &bob, &hasher),
+            Err(MerkleError::NotBracketed)
+        );
+        // even at the size the proof claims, the data of a neighbor is hashed as a leaf
+        assert_eq!(
+            forged.verify(&root, 1, &bob, &hasher),
+            Err(MerkleError::RootMismatch)
+        );
+    }
+
+    #[test]
+    fn test_interior_nodes_are_not_neighbors() {
+        let tree = create(8);
+        let hasher = Sha256Hasher {};
+        let mode = HashingMode::DomainSeparated;
+        let leaves = &tree.tree().leaves;
+
+        // the level above the leaves is itself a tree of 4 leaves with the same root
+        let nodes: Vec<Hash> = leaves
+            .chunks(2)
+            .map(|pair| hasher.get_node_hash(&pair[0].value, &pair[1].value, mode))
+            .collect();
+        let upper = CompactMerkleTree::create_from_leaf_hashes(
+            nodes.clone(),
+            Sha256Hasher {},
+            Default::default(),
+        )
+        .unwrap();
+        assert_eq!(&upper.root_hash, tree.root_hash());
+        let neighbor = |idx: usize| Neighbor {
+            data: nodes[idx].clone(),
+            proof: upper.gen_proof(idx).unwrap(),
+        };
+
+        let mut forged_count = 0;
+        for i in 0..8 {
+            let present = tree.get_leaf_hash(i.to_string());
+            let forged = match nodes.iter().position(|node| present < *node) {
+                Some(0) => bracket(neighbor(0), &nodes[0], &present),
+                None => bracket(neighbor(3), &nodes[3], &present),
+                Some(idx) if nodes[idx - 1] < present => NonMembershipProof {
+                    left: Some(neighbor(idx - 1)),
+                    right: Some(neighbor(idx)),
+                },
+                Some(_) => continue,
+            };
+            forged_count += 1;
+
+            assert_eq!(
+                forged.verify(tree.root_hash(), 8, &present, &hasher),
+                Err(MerkleError::NotBracketed)
+            );
+            assert_eq!(
+                forged.verify(tree.root_hash(), 4, &present, &hasher),
+                Err(MerkleError::RootMismatch)
+            );
+        }
+        assert!(forged_count > 0);
+    }
+
+    #[test]
+    fn test_sorted_pair_has_no_non_membership_proofs() {
+        let mode = HashingMode::SortedPair;
+        let err = MerkleError::UnsupportedHashingMode { hashing_mode: mode };
+        let data = ["alice", "bob", "carol", "dave"];
+        let tree = SortedMerkleTree::create_with_options(
+            &data,
+            Sha256Hasher {},
+            TreeOptions::openzeppelin(),
+        )
+        .unwrap();
+        assert_eq!(tree.gen_non_membership_proof("mallory"), Err(err.clone()));
+        // inclusion proofs still work
+        let proof = tree.gen_proof("bob").unwrap();
+        assert!(proof
+            .verify(
+                tree.root_hash(),
+                &tree.get_leaf_hash("bob"),
+                &tree.tree().hasher
+            )
+            .is_ok());
+
+        // nor can the neighbors of a proof be switched to sorted pairs
+        let tree = create(8);
+        let absent = tree.get_leaf_hash("absent");
+        let mut proof = tree.gen_non_membership_proof("absent").unwrap().unwrap();
+        for neighbor in proof.left.iter_mut().chain(&mut proof.right) {
+            neighbor.proof.options.hashing_mode = mode;
+        }
+        assert_eq!(
+            proof.verify(tree.root_hash(), 8, &absent, &tree.tree().hasher),
+            Err(err)
+        );
+    }
+}
@@ -0,0 +1,149 @@
+use merkle_tree::mk::sparse::{Key, SparseMerkleTree};
+use merkle_tree::utils::crypto::*;
+
+#[cfg(test)]
+mod tests {
+    use merkle_tree::error::MerkleError;
+    use merkle_tree::mk::sparse::get_default_hashes;
+
+    use super::*;
+
+    fn key(n: u8) -> Key {
+        let mut key = [0; 32];
+        key[0] = n;
+        key[31] = n;
+        key
+    }
+
+    #[test]
+    fn test_empty_tree() {
+        let tree = SparseMerkleTree::new(Sha256Hasher {});
+        let default_hashes = get_default_hashes(&tree.hasher, HashingMode::DomainSeparated);
+
+        assert!(tree.is_empty());
+        assert_eq!(tree.root_hash, default_hashes[256]);
+        assert_eq!(default_hashes[0], vec![0; 32]);
+        assert_eq!(
+            default_hashes[1],
+            tree.hasher
+                .get_node_hash(&vec![0; 32], &vec![0; 32], HashingMode::DomainSeparated)
+        );
+    }
+
+    #[test]
+    fn test_insert_get_remove() {
+        let mut tree = SparseMerkleTree::new(Sha256Hasher {});
+        let empty_root = tree.root_hash.clone();
+
+        assert_eq!(tree.insert(key(1), "hello"), None);
+        assert_eq!(tree.insert(key(2), "how"), None);
+        let root = tree.root_hash.clone();
+        assert_eq!(tree.insert(key(1), "hi"), Some(b"hello".to_vec()));
+        assert_ne!(tree.root_hash, root);
+        assert_eq!(tree.get(&key(1)), Some(&b"hi"[..]));
+        assert_eq!(tree.get(&key(3)), None);
+        assert_eq!(tree.len(), 2);
+
+        assert_eq!(tree.remove(&key(3)), None);
+        assert_eq!(tree.remove(&key(1)), Some(b"hi".to_vec()));
+        assert_eq!(tree.remove(&key(2)), Some(b"how".to_vec()));
+        // removing every value goes back to the empty tree
+        assert_eq!(tree.root_hash, empty_root);
+    }
+
+    #[test]
+    fn test_root_is_independent_of_insertion_order() {
+        let mut a = SparseMerkleTree::new(Sha256Hasher {});
+        let mut b = SparseMerkleTree::new(Sha256Hasher {});
+        for n in 0..10 {
+            a.insert(key(n), [n]);
+            b.insert(key(9 - n), [9 - n]);
+        }
+        assert_eq!(a.root_hash, b.root_hash);
+    }
+
+    #[test]
+    fn test_proofs() {
+        let mut tree = SparseMerkleTree::new(Sha256Hasher {});
+        for n in 0..10 {
+            tree.insert(key(n), [n]);
+        }
+
+        for n in 0..10 {
+            let proof = tree.gen_proof(&key(n));
+            assert!(proof
+                .verify_inclusion(&tree.root_hash, [n], &tree.hasher)
+                .is_ok());
+            assert_eq!(
+                proof.verify_inclusion(&tree.root_hash, [n + 1], &tree.hasher),
+                Err(MerkleError::RootMismatch)
+            );
+            assert!(proof
+                .verify_exclusion(&tree.root_hash, &tree.hasher)
+                .is_err());
+        }
+
+        let proof = tree.gen_proof(&key(10));
+        assert!(proof
+            .verify_exclusion(&tree.root_hash, &tree.hasher)
+            .is_ok());
+        assert!(proof
+            .verify_inclusion(&tree.root_hash, [10], &tree.hasher)
+            .is_err());
+
+        // the proof of a missing key can't be used for one that is there
+        let mut moved = proof.clone();
+        moved.key = key(3);
+        assert!(moved
+            .verify_exclusion(&tree.root_hash, &tree.hasher)
+            .is_err());
+    }
+
+    #[test]
+    fn test_proof_errors() {
+        let mut tree = SparseMerkleTree::new(Sha256Hasher {});
+        tree.insert(key(1), "hello");
+        tree.insert(key(2), "how");
+
+        let proof = tree.gen_proof(&key(1));
+        assert_eq!(proof.siblings.len(), 1);
+
+        let mut short = proof.clone();
+        short.siblings.pop();
+        assert_eq!(
+            short.verify_inclusion(&tree.root_hash, "hello", &tree.hasher),
+            Err(MerkleError::ProofLengthMismatch {
+                expected: 1,
+                actual: 0
+            })
+        );
+        assert_eq!(
+            proof.verify_inclusion(&tree.root_hash, "hello", &Blake2s256Hasher::new()),
+            Err(MerkleError::AlgorithmMismatch {
+                expected: HashAlgorithm::Blake2s256,
+                actual: HashAlgorithm::Sha256,
+            })
+        );
+    }
+
+    #[test]
+    fn test_sorted_pair_rejected() {
+        let mode = HashingMode::SortedPair;
+        let err = MerkleError::UnsupportedHashingMode { hashing_mode: mode };
+        assert_eq!(
+            SparseMerkleTree::new_with_hashing_mode(Sha256Hasher {}, mode).err(),
+            Some(err.clone())
+        );
+
+        // sorted pairs would let the exclusion proof of an empty key pass for its occupied
+        // sibling, so a proof can't be switched to them either
+        let mut tree = SparseMerkleTree::new(Sha256Hasher {});
+        tree.insert(key(1), "hello");
+        let mut proof = tree.gen_proof(&key(0));
+        proof.hashing_mode = mode;
+        assert_eq!(
+            proof.verify_exclusion(&tree.root_hash, &tree.hasher),
+            Err(err)
+        );
+    }
+}
@@ -0,0 +1,135 @@
+use merkle_tree::mk::sum::{MerkleSumTree, SumNode};
+use merkle_tree::utils::crypto::*;
+
+#[cfg(test)]
+mod tests {
+    use merkle_tree::error::MerkleError;
+
+    use super::*;
+
+    fn create(size: usize) -> MerkleSumTree<Sha256Hasher> {
+        let entries: Vec<(String, u64)> = (0..size).map(|i| (i.to_string(), i as u64)).collect();
+        MerkleSumTree::create(&entries, Sha256Hasher {}).unwrap()
+    }
+
+    #[test]
+    fn test_root() {
+        let tree = MerkleSumTree::create(&[("a", 1), ("b", 2), ("c", 3)], Sha256Hasher {}).unwrap();
+        let hasher = &tree.hasher;
+        let mode = HashingMode::DomainSeparated;
+        let [a, b, c] = [("a", 1), ("b", 2), ("c", 3)].map(|(el, sum)| tree.get_leaf_node(el, sum));
+
+        // the lone node moves up as is
+        let ab = SumNode::parent(&a, &b, hasher, mode).unwrap();
+        assert_eq!(
+            tree.root(),
+            &SumNode::parent(&ab, &c, hasher, mode).unwrap()
+        );
+        assert_eq!(tree.total(), 6);
+        assert_eq!(tree.get_leaf(2), Some(&c));
+
+        // the balance is part of the leaf hash
+        assert_ne!(a.hash, tree.get_leaf_node("a", 2).hash);
+        assert_eq!(
+            MerkleSumTree::<Sha256Hasher>::create::<&str>(&[], Sha256Hasher {}).err(),
+            Some(MerkleError::EmptyInput)
+        );
+    }
+
+    #[test]
+    fn test_proofs() {
+        for size in 1..20 {
+            let tree = create(size);
+            assert_eq!(tree.total(), (0..size as u64).sum::<u64>());
+            for idx in 0..size {
+                let proof = tree.gen_proof(idx).unwrap();
+                let leaf = tree.get_leaf_node(idx.to_string(), idx as u64);
+                assert!(proof.verify(tree.root(), &leaf, &tree.hasher).is_ok());
+            }
+        }
+    }
+
+    #[test]
+    fn test_proof_fails() {
+        let tree = create(5);
+        let proof = tree.gen_proof(3).unwrap();
+        let leaf = tree.get_leaf_node("3", 3);
+
+        // a lower balance doesn't lead to the root
+        let understated = tree.get_leaf_node("3", 2);
+        assert_eq!(
+            proof.verify(tree.root(), &understated, &tree.hasher),
+            Err(MerkleError::RootMismatch)
+        );
+
+        // neither does a sibling hiding part of its sum
+        let mut hidden = proof.clone();
+        hidden.path[0].sum -= 1;
+        assert_eq!(
+            hidden.verify(tree.root(), &leaf, &tree.hasher),
+            Err(MerkleError::RootMismatch)
+        );
+
+        // nor the right hash with another total
+        let root = SumNode {
+            hash: tree.root().hash.clone(),
+            sum: tree.total() - 1,
+        };
+        assert_eq!(
+            proof.verify(&root, &leaf, &tree.hasher),
+            Err(MerkleError::RootMismatch)
+        );
+
+        let mut short = proof.clone();
+        short.path.pop();
+        assert_eq!(
+            short.verify(tree.root(), &leaf, &tree.hasher),
+            Err(MerkleError::ProofLengthMismatch {
+                expected: 3,
+                actual: 2
+            })
+        );
+        assert_eq!(
+            tree.gen_proof(5).err(),
+            Some(MerkleError::IndexOutOfRange { index: 5, len: 5 })
+        );
+    }
+
+    #[test]
+    fn test_sum_overflow() {
+        assert_eq!(
+            MerkleSumTree::create(&[("a", u64::MAX), ("b", 1)], Sha256Hasher {}).err(),
+            Some(MerkleError::SumOverflow)
+        );
+
+        // a sibling claiming a huge sum can't wrap the total around
+        let tree = create(4);
+        let mut proof = tree.gen_proof(0).unwrap();
+        proof.path[1].sum = u64::MAX;
+        let leaf = tree.get_leaf_node("0", 0);
+        assert_eq!(
+            proof.verify(tree.root(), &leaf, &tree.hasher),
+            Err(MerkleError::SumOverflow)
+        );
+    }
+
+    #[test]
+    fn test_sorted_pair_rejected() {
+        let mode = HashingMode::SortedPair;
+        let err = Some(MerkleError::UnsupportedHashingMode { hashing_mode: mode });
+        for size in [1, 4] {
+            let entries: Vec<(String, u64)> = (0..size).map(|i| (i.to_string(), i)).collect();
+            assert_eq!(
+                MerkleSumTree::create_with_hashing_mode(&entries, Sha256Hasher {}, mode).err(),
+                err
+            );
+        }
+
+        // nor can a proof be switched to it
+        let tree = create(4);
+        let mut proof = tree.gen_proof(0).unwrap();
+        proof.hashing_mode = mode;
+        let leaf = tree.get_leaf_node("0", 0);
+        assert_eq!(proof.verify(tree.root(), &leaf, &tree.hasher).err(), err);
+    }
+}
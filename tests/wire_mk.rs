@@ -0,0 +1,214 @@
+use merkle_tree::mk::proof::MerkleProof;
+use merkle_tree::mk::wire::{HEADER_LEN, VERSION};
+use merkle_tree::utils::crypto::*;
+
+#[cfg(test)]
+mod tests {
+    use merkle_tree::error::MerkleError;
+    use merkle_tree::mk::compact::CompactMerkleTree;
+    use merkle_tree::mk::mk::MerkleTree;
+    use merkle_tree::mk::options::{OddNodePolicy, TreeOptions};
+
+    use super::*;
+
+    const POLICIES: [OddNodePolicy; 3] = [
+        OddNodePolicy::Duplicate,
+        OddNodePolicy::Promote,
+        OddNodePolicy::PadToPowerOfTwo,
+    ];
+
+    fn get_proof(size: usize, leaf_idx: usize) -> MerkleProof {
+        let data: Vec<String> = (0..size).map(|i| i.to_string()).collect();
+        let tree = CompactMerkleTree::create(&data, Sha256Hasher {}).unwrap();
+        tree.gen_proof(leaf_idx).unwrap()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let data: Vec<String> = (0..20).map(|i| i.to_string()).collect();
+        for (policy, branching_factor) in POLICIES
+            .into_iter()
+            .flat_map(|policy| [2, 3, 4].map(|branching_factor| (policy, branching_factor)))
+        {
+            let options = TreeOptions {
+                odd_node_policy: policy,
+                branching_factor,
+                ..Default::default()
+            };
+            for size in 1..=data.len() {
+                let tree =
+                    CompactMerkleTree::create_with_options(&data[..size], Sha256Hasher {}, options)
+                        .unwrap();
+                for (idx, el) in data[..size].iter().enumerate() {
+                    let proof = tree.gen_proof(idx).unwrap();
+                    let bytes = proof.encode().unwrap();
+                    assert_eq!(bytes.len(), HEADER_LEN + 32 * proof.sibling_hashes().len());
+
+                    let decoded = MerkleProof::decode(&bytes).unwrap();
+                    assert_eq!(decoded, proof);
+                    let leaf_hash = tree.get_leaf_hash(el);
+                    assert!(decoded
+                        .verify(&tree.root_hash, &leaf_hash, &Sha256Hasher {})
+                        .is_ok());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_encoding_layout() {
+        let proof = get_proof(5, 2);
+        let bytes = proof.encode().unwrap();
+        let siblings = proof.sibling_hashes();
+
+        assert_eq!(
+            bytes[..HEADER_LEN],
+            [
+                VERSION, 1, 1, 0, // version, sha256, domain separated, duplicate
+                0, 2, 0, 32, // branching factor, hash length
+                0, 0, 0, 0, 0, 0, 0, 2, // leaf index
+                0, 0, 0, 0, 0, 0, 0, 5, // tree size
+                0, 0, 0, 0, 0, 0, 0, 0b010, // right child on the second level only
+            ]
+        );
+        assert_eq!(bytes[HEADER_LEN..], siblings.concat());
+    }
+
+    #[test]
+    fn test_single_leaf() {
+        let proof = get_proof(1, 0);
+        let bytes = proof.encode().unwrap();
+
+        assert_eq!(bytes.len(), HEADER_LEN);
+        // no siblings, so no hash length
+        assert_eq!(bytes[6..8], [0, 0]);
+        assert_eq!(MerkleProof::decode(&bytes).unwrap(), proof);
+
+        let mut with_hash_len = bytes.clone();
+        with_hash_len[7] = 32;
+        assert_eq!(
+            MerkleProof::decode(&with_hash_len),
+            Err(MerkleError::InvalidEncoding)
+        );
+    }
+
+    #[test]
+    fn test_decode_invalid_lengths() {
+        let bytes = get_proof(5, 2).encode().unwrap();
+        let len = bytes.len();
+
+        assert_eq!(
+            MerkleProof::decode(&bytes[..HEADER_LEN - 1]),
+            Err(MerkleError::EncodingLengthMismatch {
+                expected: HEADER_LEN,
+                actual: HEADER_LEN - 1,
+            })
+        );
+        assert_eq!(
+            MerkleProof::decode(&bytes[..len - 1]),
+            Err(MerkleError::EncodingLengthMismatch {
+                expected: len,
+                actual: len - 1,
+            })
+        );
+        let padded = [bytes.as_slice(), &[0]].concat();
+        assert_eq!(
+            MerkleProof::decode(&padded),
+            Err(MerkleError::EncodingLengthMismatch {
+                expected: len,
+                actual: len + 1,
+            })
+        );
+
+        // the siblings would have to be 31 bytes long
+        let mut shorter_hashes = bytes.clone();
+        shorter_hashes[7] = 31;
+        assert_eq!(
+            MerkleProof::decode(&shorter_hashes),
+            Err(MerkleError::EncodingLengthMismatch {
+                expected: HEADER_LEN + 3 * 31,
+                actual: len,
+            })
+        );
+        // a bigger tree has a longer path
+        let mut bigger_tree = bytes.clone();
+        bigger_tree[23] = 9;
+        assert_eq!(
+            MerkleProof::decode(&bigger_tree),
+            Err(MerkleError::EncodingLengthMismatch {
+                expected: HEADER_LEN + 4 * 32,
+                actual: len,
+            })
+        );
+    }
+
+    #[test]
+    fn test_decode_invalid_header() {
+        let bytes = get_proof(5, 2).encode().unwrap();
+        let decode_with = |offset: usize, value: u8| {
+            let mut bytes = bytes.clone();
+            bytes[offset] = value;
+            MerkleProof::decode(&bytes)
+        };
+
+        assert_eq!(
+            decode_with(0, 2),
+            Err(MerkleError::UnsupportedVersion { version: 2 })
+        );
+        assert_eq!(decode_with(1, 42), Err(MerkleError::InvalidEncoding));
+        assert_eq!(decode_with(2, 3), Err(MerkleError::InvalidEncoding));
+        assert_eq!(decode_with(3, 3), Err(MerkleError::InvalidEncoding));
+        assert_eq!(
+            decode_with(5, 1),
+            Err(MerkleError::InvalidBranchingFactor {
+                branching_factor: 1
+            })
+        );
+        assert_eq!(
+            decode_with(15, 5),
+            Err(MerkleError::IndexOutOfRange { index: 5, len: 5 })
+        );
+        // the bitmap doesn't match the position of the leaf
+        assert_eq!(
+            decode_with(31, 0b011),
+            Err(MerkleError::PositionMismatch { step: 0 })
+        );
+        assert_eq!(
+            decode_with(31, 0b1010),
+            Err(MerkleError::PositionMismatch { step: 3 })
+        );
+        // another algorithm decodes, but doesn't verify with this hasher
+        let proof = decode_with(1, HashAlgorithm::Keccak256.id()).unwrap();
+        assert_eq!(proof.algorithm, HashAlgorithm::Keccak256);
+    }
+
+    #[test]
+    fn test_encode_invalid_proof() {
+        let mut proof = get_proof(5, 2);
+        proof.path[1].siblings[0].pop();
+        assert_eq!(
+            proof.encode(),
+            Err(MerkleError::HashLengthMismatch {
+                expected: 32,
+                actual: 31,
+            })
+        );
+
+        let mut proof = get_proof(5, 2);
+        proof.path[0].position = 1;
+        assert_eq!(
+            proof.encode(),
+            Err(MerkleError::PositionMismatch { step: 0 })
+        );
+
+        let mut proof = get_proof(5, 2);
+        proof.path.pop();
+        assert_eq!(
+            proof.encode(),
+            Err(MerkleError::ProofLengthMismatch {
+                expected: 3,
+                actual: 2,
+            })
+        );
+    }
+}